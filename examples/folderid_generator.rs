@@ -8,6 +8,13 @@ fn main() {
         ("FOLDERID_Videos", "18989B1D-99B5-455B-841C-AB7C74E4DDFC"),  // MoviesLocation
         ("FOLDERID_Pictures", "33E28130-4E1E-4676-835A-98395C3BC3BB"), // PicturesLocation
         ("FOLDERID_Downloads", "374DE290-123F-4565-9164-39C4925E467B"), // DownloadLocation
+        ("FOLDERID_Templates", "A63293E8-664E-48DB-A079-DF759E0509F7"), // TemplatesLocation
+        ("FOLDERID_Public", "DFDF76A2-C82A-4D63-906A-5644AC457385"),  // PublicShareLocation
+        // UserExecutablesLocation
+        (
+            "FOLDERID_UserProgramFiles",
+            "5CD7AEE2-2219-4A67-B85D-6C9CE15660CB",
+        ),
         // AppLocalDataLocation, AppLocalDataLocation,
         // GenericDataLocation, ConfigLocation,
         // GenericConfigLocation, AppConfigLocation
@@ -23,12 +23,16 @@ fn main() {
             .add_argument("file", Store, "file name to search")
             .required();
         ap.refer(&mut location)
-            .add_argument("location", Store, "type of location")
+            .add_argument(
+                "location",
+                Store,
+                "type of location, e.g. 'AppConfigLocation' or 'app-config'",
+            )
             .required();
         ap.refer(&mut option).add_option(
             &["-o", "--option"],
             Store,
-            "locate option, default is 'LocateFile'",
+            "locate flags, e.g. 'LocateFile | Readable', default is 'LocateFile'",
         );
         ap.refer(&mut locate_all).add_option(
             &["-a", "--all"],
@@ -38,38 +42,18 @@ fn main() {
         ap.parse_args_or_exit();
     }
 
-    let location = match location.as_str() {
-        "HomeLocation" => LocationType::HomeLocation,
-        "DesktopLocation" => LocationType::DesktopLocation,
-        "DocumentsLocation" => LocationType::DocumentsLocation,
-        "DownloadLocation" => LocationType::DownloadLocation,
-        "MoviesLocation" => LocationType::MoviesLocation,
-        "MusicLocation" => LocationType::MusicLocation,
-        "PicturesLocation" => LocationType::PicturesLocation,
-        "ApplicationsLocation" => LocationType::ApplicationsLocation,
-        "FontsLocation" => LocationType::FontsLocation,
-        "RuntimeLocation" => LocationType::RuntimeLocation,
-        "TempLocation" => LocationType::TempLocation,
-        "GenericDataLocation" => LocationType::GenericDataLocation,
-        "AppDataLocation" => LocationType::AppDataLocation,
-        "AppLocalDataLocation" => LocationType::AppLocalDataLocation,
-        "GenericCacheLocation" => LocationType::GenericCacheLocation,
-        "AppCacheLocation" => LocationType::AppCacheLocation,
-        "ConfigLocation" => LocationType::ConfigLocation,
-        "GenericConfigLocation" => LocationType::GenericConfigLocation,
-        "AppConfigLocation" => LocationType::AppConfigLocation,
-        _ => {
-            eprintln!("Bad location type '{location}', see the documentation for valid values");
+    let location = match location.parse::<LocationType>() {
+        Ok(location) => location,
+        Err(err) => {
+            eprintln!("{err}, see the documentation for valid values");
             process::exit(1)
         }
     };
 
-    let option = match option.as_str() {
-        "LocateBoth" => LocateOption::LocateBoth,
-        "LocateFile" => LocateOption::LocateFile,
-        "LocateDirectory" => LocateOption::LocateDirectory,
-        _ => {
-            eprintln!("Bad locate option '{option}', see the documentation for valid values");
+    let option = match option.parse::<LocateOption>() {
+        Ok(option) => option,
+        Err(err) => {
+            eprintln!("{err}, see the documentation for valid values");
             process::exit(1)
         }
     };
@@ -96,8 +80,8 @@ fn main() {
     } else {
         match sp.locate(location, &file, option) {
             Ok(path) => {
-                if path.is_some() {
-                    println!(r#""{}""#, path.unwrap().to_str().unwrap());
+                if let Some(path) = path {
+                    println!(r#""{}""#, path.to_str().unwrap());
                     process::exit(0)
                 }
             }
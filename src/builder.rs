@@ -0,0 +1,498 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    overrides::env_prefix_from_app, test_mode::TestRoot, Environment, FileSystem, LocationType,
+    ProcessEnvironment, StandardPaths, StdFileSystem,
+};
+
+/// Enumerates the ways the application-specific suffix is built.
+///
+/// Is used to configure
+/// [`StandardPathsBuilder::naming_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NamingPolicy {
+    /// Append the organization and the application names, e.g. `~/.config/org/app`.
+    ///
+    /// The organization is skipped if it is empty. This is the default value.
+    #[default]
+    OrganizationAndApplication,
+    /// Append only the application name, e.g. `~/.config/app`.
+    Application,
+    /// Append a single reverse domain name identifier, e.g. `~/.config/com.example.app`.
+    ///
+    /// Falls back to [`NamingPolicy::OrganizationAndApplication`]
+    /// if the organization domain is empty.
+    ReverseDomain,
+}
+
+/// Enumerates the ways the XDG base directory environment variables
+/// and the `user-dirs.dirs` files are validated.
+///
+/// Is used to configure
+/// [`StandardPathsBuilder::strictness`] and
+/// [`UserDirs::parse_with_strictness`](crate::UserDirs::parse_with_strictness).
+/// The builder setting only affects Linux.
+///
+/// In both modes the empty values are ignored as if the variables were unset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// Accept relative paths and expand a leading `~` to the home directory.
+    ///
+    /// Skip the malformed lines of a `user-dirs.dirs` file.
+    ///
+    /// This is the default value.
+    #[default]
+    Lenient,
+    /// Follow the XDG Base Directory Specification and ignore the relative paths
+    /// in `XDG_CONFIG_HOME`, `XDG_DATA_HOME`, `XDG_CACHE_HOME`, `XDG_STATE_HOME`,
+    /// `XDG_RUNTIME_DIR`, `XDG_CONFIG_DIRS` and `XDG_DATA_DIRS`.
+    ///
+    /// Fail on a malformed line of a `user-dirs.dirs` file instead of skipping it.
+    Strict,
+}
+
+/// The source of the override variables prefix.
+enum EnvPrefix {
+    /// Derived from the application name.
+    FromAppName,
+    /// Set explicitly.
+    Custom(String),
+}
+
+/// Configures and constructs a [`StandardPaths`].
+///
+/// Is returned by [`StandardPaths::builder`].
+///
+/// ### Example
+/// ```
+/// use standard_paths::{LocationType, MapEnvironment, NamingPolicy, StandardPaths};
+/// use std::path::PathBuf;
+///
+/// let sp = StandardPaths::builder()
+///     .app_name("app")
+///     .org_name("Example")
+///     .org_domain("example.com")
+///     .naming_policy(NamingPolicy::ReverseDomain)
+///     .environment(MapEnvironment::new().with_home("/home/user"))
+///     .build();
+/// assert_eq!(sp.domain(), "example.com");
+/// assert_eq!(
+///     sp.writable_location(LocationType::AppConfigLocation).unwrap(),
+///     PathBuf::from("/home/user/.config/com.example.app")
+/// );
+/// ```
+pub struct StandardPathsBuilder {
+    /// Application name.
+    app_name: String,
+    /// Organization name.
+    org_name: String,
+    /// Organization domain.
+    domain: String,
+    /// Application suffix policy.
+    naming: NamingPolicy,
+    /// Environment variables validation mode.
+    strictness: Strictness,
+    /// Additional search directories.
+    search_dirs: Vec<(LocationType, PathBuf)>,
+    /// Per-location overrides.
+    overrides: Vec<(LocationType, PathBuf)>,
+    /// Override variables prefix.
+    env_prefix: Option<EnvPrefix>,
+    /// Test mode sandbox root.
+    test_root: Option<TestRoot>,
+    /// Environment the locations are resolved in.
+    env: Arc<dyn Environment>,
+    /// Filesystem the lookups are performed on.
+    fs: Arc<dyn FileSystem>,
+}
+
+impl Default for StandardPathsBuilder {
+    fn default() -> Self {
+        StandardPathsBuilder {
+            app_name: Default::default(),
+            org_name: Default::default(),
+            domain: Default::default(),
+            naming: Default::default(),
+            strictness: Default::default(),
+            search_dirs: Default::default(),
+            overrides: Default::default(),
+            env_prefix: Default::default(),
+            test_root: Default::default(),
+            env: Arc::new(ProcessEnvironment),
+            fs: Arc::new(StdFileSystem),
+        }
+    }
+}
+
+impl StandardPathsBuilder {
+    /// Constructs a new [`StandardPathsBuilder`] with the default settings.
+    pub fn new() -> StandardPathsBuilder {
+        Default::default()
+    }
+
+    /// Sets the application name.
+    pub fn app_name<S>(mut self, name: S) -> StandardPathsBuilder
+    where
+        S: Into<String>,
+    {
+        self.app_name = name.into();
+        self
+    }
+
+    /// Sets the organization name.
+    pub fn org_name<S>(mut self, name: S) -> StandardPathsBuilder
+    where
+        S: Into<String>,
+    {
+        self.org_name = name.into();
+        self
+    }
+
+    /// Sets the organization domain, e.g. `example.com`.
+    pub fn org_domain<S>(mut self, domain: S) -> StandardPathsBuilder
+    where
+        S: Into<String>,
+    {
+        self.domain = domain.into();
+        self
+    }
+
+    /// Sets the way the application-specific suffix is built.
+    pub fn naming_policy(mut self, policy: NamingPolicy) -> StandardPathsBuilder {
+        self.naming = policy;
+        self
+    }
+
+    /// Sets the way the XDG base directory environment variables are validated.
+    ///
+    /// See [`StandardPaths::validate_environment`] to list the ignored values.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, MapEnvironment, StandardPaths, Strictness};
+    /// use std::path::PathBuf;
+    ///
+    /// let env = MapEnvironment::new()
+    ///     .with_home("/home/user")
+    ///     .with_var("XDG_CONFIG_HOME", "~/conf");
+    /// let sp = StandardPaths::builder()
+    ///     .environment(env.clone())
+    ///     .build();
+    /// # #[cfg(target_os = "linux")]
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::GenericConfigLocation).unwrap(),
+    ///     PathBuf::from("/home/user/conf")
+    /// );
+    ///
+    /// let sp = StandardPaths::builder()
+    ///     .environment(env)
+    ///     .strictness(Strictness::Strict)
+    ///     .build();
+    /// # #[cfg(target_os = "linux")]
+    /// # {
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::GenericConfigLocation).unwrap(),
+    ///     PathBuf::from("/home/user/.config")
+    /// );
+    /// assert_eq!(sp.validate_environment().len(), 1);
+    /// # }
+    /// ```
+    pub fn strictness(mut self, strictness: Strictness) -> StandardPathsBuilder {
+        self.strictness = strictness;
+        self
+    }
+
+    /// Adds an extra directory to search for the `location`.
+    ///
+    /// The extra directories are appended to the
+    /// [`StandardPaths::standard_locations`] result in the order they were added,
+    /// so they have the lowest priority.
+    pub fn search_dir<P>(mut self, location: LocationType, path: P) -> StandardPathsBuilder
+    where
+        P: Into<PathBuf>,
+    {
+        self.search_dirs.push((location, path.into()));
+        self
+    }
+
+    /// Overrides the `location` with the `path`.
+    ///
+    /// The path is returned by [`StandardPaths::writable_location`] and replaces
+    /// the first entry of [`StandardPaths::standard_locations`], so it's also
+    /// searched first by [`StandardPaths::locate`].
+    /// The override is ignored in the test mode.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, MapEnvironment, StandardPaths};
+    /// use std::path::PathBuf;
+    ///
+    /// let sp = StandardPaths::builder()
+    ///     .app_name("app")
+    ///     .environment(MapEnvironment::new().with_home("/home/user"))
+    ///     .override_location(LocationType::AppCacheLocation, "/var/cache/app")
+    ///     .build();
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::AppCacheLocation).unwrap(),
+    ///     PathBuf::from("/var/cache/app")
+    /// );
+    /// ```
+    pub fn override_location<P>(mut self, location: LocationType, path: P) -> StandardPathsBuilder
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        match self.overrides.iter_mut().find(|(loc, _)| *loc == location) {
+            Some(entry) => entry.1 = path,
+            _ => self.overrides.push((location, path)),
+        }
+        self
+    }
+
+    /// Enables the overrides from the app-scoped environment variables.
+    ///
+    /// The variables names are prefixed with the application name converted
+    /// to the upper case with all the non-alphanumeric characters replaced by `_`.
+    /// For example `MYAPP_CONFIG_DIR=/srv/myapp/conf` overrides
+    /// [`LocationType::AppConfigLocation`] for the `myapp` application.
+    /// See [`StandardPaths::override_env_var`] for the names of the variables.
+    /// The variables are ignored in the test mode.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, MapEnvironment, StandardPaths};
+    /// use std::path::PathBuf;
+    ///
+    /// let env = MapEnvironment::new()
+    ///     .with_home("/home/user")
+    ///     .with_var("MYAPP_CONFIG_DIR", "/srv/myapp/conf");
+    /// let sp = StandardPaths::builder()
+    ///     .app_name("myapp")
+    ///     .environment(env)
+    ///     .env_overrides()
+    ///     .build();
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::AppConfigLocation).unwrap(),
+    ///     PathBuf::from("/srv/myapp/conf")
+    /// );
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::GenericConfigLocation).unwrap(),
+    ///     PathBuf::from("/home/user/.config")
+    /// );
+    /// ```
+    pub fn env_overrides(mut self) -> StandardPathsBuilder {
+        self.env_prefix = Some(EnvPrefix::FromAppName);
+        self
+    }
+
+    /// Enables the overrides from the environment variables with a custom `prefix`.
+    ///
+    /// See [`StandardPathsBuilder::env_overrides`] for details.
+    pub fn env_overrides_prefix<S>(mut self, prefix: S) -> StandardPathsBuilder
+    where
+        S: Into<String>,
+    {
+        self.env_prefix = Some(EnvPrefix::Custom(prefix.into()));
+        self
+    }
+
+    /// Enables or disables the test mode for the instance.
+    ///
+    /// In the test mode every writable location is redirected into the `~/.qttest` sandbox
+    /// or into `.qttest` in the temporary directory if the home directory is unknown.
+    /// See [`StandardPaths::test_root`] for the sandbox layout.
+    pub fn test_mode(mut self, enabled: bool) -> StandardPathsBuilder {
+        self.test_root = if enabled {
+            Some(TestRoot::Default)
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Enables the test mode for the instance with the sandbox located at `root`,
+    /// e.g. a temporary directory.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, StandardPaths};
+    ///
+    /// let root = std::env::temp_dir().join("standard_paths_doc_test_root");
+    /// let sp = StandardPaths::builder()
+    ///     .app_name("app")
+    ///     .org_name("org")
+    ///     .test_root(&root)
+    ///     .build();
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::AppConfigLocation).unwrap(),
+    ///     root.join("config/org/app")
+    /// );
+    /// # #[cfg(target_os = "linux")]
+    /// assert_eq!(
+    ///     sp.writable_location(LocationType::DesktopLocation).unwrap(),
+    ///     root.join("home/Desktop")
+    /// );
+    /// # std::fs::remove_dir_all(&root).ok();
+    /// ```
+    pub fn test_root<P>(mut self, root: P) -> StandardPathsBuilder
+    where
+        P: Into<PathBuf>,
+    {
+        self.test_root = Some(TestRoot::Custom(root.into()));
+        self
+    }
+
+    /// Sets the environment the locations are resolved in.
+    ///
+    /// The default is [`ProcessEnvironment`].
+    pub fn environment<E>(mut self, env: E) -> StandardPathsBuilder
+    where
+        E: Environment + 'static,
+    {
+        self.env = Arc::new(env);
+        self
+    }
+
+    /// Sets the filesystem the lookups are performed on.
+    ///
+    /// The default is [`StdFileSystem`].
+    pub fn file_system<F>(mut self, fs: F) -> StandardPathsBuilder
+    where
+        F: FileSystem + 'static,
+    {
+        self.fs = Arc::new(fs);
+        self
+    }
+
+    /// Constructs a new [`StandardPaths`] with the configured settings.
+    pub fn build(self) -> StandardPaths {
+        let env_prefix = match self.env_prefix {
+            Some(EnvPrefix::FromAppName) => Some(env_prefix_from_app(&self.app_name)),
+            Some(EnvPrefix::Custom(prefix)) => Some(prefix),
+            _ => None,
+        };
+        StandardPaths {
+            app_name: self.app_name,
+            org_name: self.org_name,
+            domain: self.domain,
+            naming: self.naming,
+            strictness: self.strictness,
+            search_dirs: self.search_dirs,
+            overrides: self.overrides,
+            env_prefix,
+            test_root: self.test_root,
+            env: self.env,
+            fs: self.fs,
+            #[cfg(target_os = "linux")]
+            prepared_test_root: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::MapEnvironment;
+
+    fn suffix(builder: StandardPathsBuilder) -> PathBuf {
+        let mut path = PathBuf::new();
+        builder.build().append_organization_and_app(&mut path);
+        path
+    }
+
+    #[test]
+    fn settings() {
+        let sp = StandardPaths::builder()
+            .app_name("app")
+            .org_name("org")
+            .org_domain("example.com")
+            .naming_policy(NamingPolicy::Application)
+            .strictness(Strictness::Strict)
+            .build();
+        assert_eq!(sp.app_name(), "app");
+        assert_eq!(sp.org_name(), "org");
+        assert_eq!(sp.domain(), "example.com");
+        assert_eq!(sp.naming_policy(), NamingPolicy::Application);
+        assert_eq!(sp.strictness(), Strictness::Strict);
+        assert!(!sp.is_test_mode_enabled());
+    }
+
+    #[test]
+    fn naming_policies() {
+        let builder = || {
+            StandardPaths::builder()
+                .app_name("app")
+                .org_name("org")
+                .org_domain("example.com")
+        };
+        assert_eq!(suffix(builder()), Path::new("org/app"));
+        assert_eq!(
+            suffix(builder().naming_policy(NamingPolicy::Application)),
+            Path::new("app")
+        );
+        assert_eq!(
+            suffix(builder().naming_policy(NamingPolicy::ReverseDomain)),
+            Path::new("com.example.app")
+        );
+        assert_eq!(
+            suffix(
+                builder()
+                    .org_domain("")
+                    .naming_policy(NamingPolicy::ReverseDomain)
+            ),
+            Path::new("org/app")
+        );
+        assert_eq!(
+            suffix(StandardPaths::builder().app_name("app")),
+            Path::new("app")
+        );
+        assert_eq!(suffix(StandardPaths::builder()), Path::new(""));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn search_dirs() {
+        let sp = StandardPaths::builder()
+            .app_name("app")
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("XDG_CONFIG_DIRS", "/etc/xdg"),
+            )
+            .search_dir(LocationType::AppConfigLocation, "/opt/app/etc")
+            .search_dir(LocationType::AppCacheLocation, "/var/cache/app")
+            .search_dir(LocationType::AppConfigLocation, "/home/user/.config/app")
+            .search_dir(LocationType::AppConfigLocation, "/srv/app")
+            .build();
+        assert_eq!(
+            sp.standard_locations(LocationType::AppConfigLocation)
+                .unwrap(),
+            [
+                PathBuf::from("/home/user/.config/app"),
+                PathBuf::from("/etc/xdg/app"),
+                PathBuf::from("/opt/app/etc"),
+                PathBuf::from("/srv/app"),
+            ]
+        );
+        // The search directories are never writable
+        assert_eq!(
+            sp.writable_location(LocationType::AppCacheLocation)
+                .unwrap(),
+            Path::new("/home/user/.cache/app")
+        );
+    }
+
+    #[test]
+    fn override_replaces_previous_one() {
+        let sp = StandardPaths::builder()
+            .override_location(LocationType::AppDataLocation, "/a")
+            .override_location(LocationType::AppDataLocation, "/b")
+            .build();
+        assert_eq!(
+            sp.location_override(LocationType::AppDataLocation),
+            Some(PathBuf::from("/b"))
+        );
+    }
+}
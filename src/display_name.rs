@@ -0,0 +1,323 @@
+use crate::{Environment, LocationType, StandardPaths};
+
+/// A table of the location display names in a single language.
+type Translation = fn(LocationType) -> &'static str;
+
+/// The built-in translations by language code.
+const TRANSLATIONS: [(&str, Translation); 5] = [
+    ("en", english),
+    ("de", german),
+    ("fr", french),
+    ("es", spanish),
+    ("ru", russian),
+];
+
+/// The environment variables which define the messages locale in the order of precedence.
+const LOCALE_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+fn english(location: LocationType) -> &'static str {
+    match location {
+        LocationType::HomeLocation => "Home",
+        LocationType::DesktopLocation => "Desktop",
+        LocationType::DocumentsLocation => "Documents",
+        LocationType::DownloadLocation => "Download",
+        LocationType::MoviesLocation => "Movies",
+        LocationType::MusicLocation => "Music",
+        LocationType::PicturesLocation => "Pictures",
+        LocationType::ApplicationsLocation => "Applications",
+        LocationType::FontsLocation => "Fonts",
+        LocationType::RuntimeLocation => "Runtime",
+        LocationType::TempLocation => "Temporary Directory",
+        LocationType::GenericDataLocation => "Shared Data",
+        LocationType::AppDataLocation => "Application Data",
+        LocationType::AppLocalDataLocation => "Application Data",
+        LocationType::GenericCacheLocation => "Shared Cache",
+        LocationType::AppCacheLocation => "Cache",
+        LocationType::ConfigLocation => "Configuration",
+        LocationType::GenericConfigLocation => "Shared Configuration",
+        LocationType::AppConfigLocation => "Application Configuration",
+        LocationType::GenericStateLocation => "Shared State",
+        LocationType::AppStateLocation => "Application State",
+        LocationType::TemplatesLocation => "Templates",
+        LocationType::PublicShareLocation => "Public",
+        LocationType::UserExecutablesLocation => "Executables",
+    }
+}
+
+fn german(location: LocationType) -> &'static str {
+    match location {
+        LocationType::HomeLocation => "Persönlicher Ordner",
+        LocationType::DesktopLocation => "Schreibtisch",
+        LocationType::DocumentsLocation => "Dokumente",
+        LocationType::DownloadLocation => "Downloads",
+        LocationType::MoviesLocation => "Filme",
+        LocationType::MusicLocation => "Musik",
+        LocationType::PicturesLocation => "Bilder",
+        LocationType::ApplicationsLocation => "Anwendungen",
+        LocationType::FontsLocation => "Schriftarten",
+        LocationType::RuntimeLocation => "Laufzeit",
+        LocationType::TempLocation => "Temporäres Verzeichnis",
+        LocationType::GenericDataLocation => "Gemeinsame Daten",
+        LocationType::AppDataLocation => "Anwendungsdaten",
+        LocationType::AppLocalDataLocation => "Anwendungsdaten",
+        LocationType::GenericCacheLocation => "Gemeinsamer Cache",
+        LocationType::AppCacheLocation => "Cache",
+        LocationType::ConfigLocation => "Konfiguration",
+        LocationType::GenericConfigLocation => "Gemeinsame Konfiguration",
+        LocationType::AppConfigLocation => "Anwendungskonfiguration",
+        LocationType::GenericStateLocation => "Gemeinsamer Zustand",
+        LocationType::AppStateLocation => "Anwendungszustand",
+        LocationType::TemplatesLocation => "Vorlagen",
+        LocationType::PublicShareLocation => "Öffentlich",
+        LocationType::UserExecutablesLocation => "Programme",
+    }
+}
+
+fn french(location: LocationType) -> &'static str {
+    match location {
+        LocationType::HomeLocation => "Dossier personnel",
+        LocationType::DesktopLocation => "Bureau",
+        LocationType::DocumentsLocation => "Documents",
+        LocationType::DownloadLocation => "Téléchargements",
+        LocationType::MoviesLocation => "Films",
+        LocationType::MusicLocation => "Musique",
+        LocationType::PicturesLocation => "Images",
+        LocationType::ApplicationsLocation => "Applications",
+        LocationType::FontsLocation => "Polices",
+        LocationType::RuntimeLocation => "Exécution",
+        LocationType::TempLocation => "Répertoire temporaire",
+        LocationType::GenericDataLocation => "Données partagées",
+        LocationType::AppDataLocation => "Données de l'application",
+        LocationType::AppLocalDataLocation => "Données de l'application",
+        LocationType::GenericCacheLocation => "Cache partagé",
+        LocationType::AppCacheLocation => "Cache",
+        LocationType::ConfigLocation => "Configuration",
+        LocationType::GenericConfigLocation => "Configuration partagée",
+        LocationType::AppConfigLocation => "Configuration de l'application",
+        LocationType::GenericStateLocation => "État partagé",
+        LocationType::AppStateLocation => "État de l'application",
+        LocationType::TemplatesLocation => "Modèles",
+        LocationType::PublicShareLocation => "Public",
+        LocationType::UserExecutablesLocation => "Exécutables",
+    }
+}
+
+fn spanish(location: LocationType) -> &'static str {
+    match location {
+        LocationType::HomeLocation => "Carpeta personal",
+        LocationType::DesktopLocation => "Escritorio",
+        LocationType::DocumentsLocation => "Documentos",
+        LocationType::DownloadLocation => "Descargas",
+        LocationType::MoviesLocation => "Películas",
+        LocationType::MusicLocation => "Música",
+        LocationType::PicturesLocation => "Imágenes",
+        LocationType::ApplicationsLocation => "Aplicaciones",
+        LocationType::FontsLocation => "Fuentes",
+        LocationType::RuntimeLocation => "Tiempo de ejecución",
+        LocationType::TempLocation => "Directorio temporal",
+        LocationType::GenericDataLocation => "Datos compartidos",
+        LocationType::AppDataLocation => "Datos de la aplicación",
+        LocationType::AppLocalDataLocation => "Datos de la aplicación",
+        LocationType::GenericCacheLocation => "Caché compartida",
+        LocationType::AppCacheLocation => "Caché",
+        LocationType::ConfigLocation => "Configuración",
+        LocationType::GenericConfigLocation => "Configuración compartida",
+        LocationType::AppConfigLocation => "Configuración de la aplicación",
+        LocationType::GenericStateLocation => "Estado compartido",
+        LocationType::AppStateLocation => "Estado de la aplicación",
+        LocationType::TemplatesLocation => "Plantillas",
+        LocationType::PublicShareLocation => "Público",
+        LocationType::UserExecutablesLocation => "Ejecutables",
+    }
+}
+
+fn russian(location: LocationType) -> &'static str {
+    match location {
+        LocationType::HomeLocation => "Домашняя папка",
+        LocationType::DesktopLocation => "Рабочий стол",
+        LocationType::DocumentsLocation => "Документы",
+        LocationType::DownloadLocation => "Загрузки",
+        LocationType::MoviesLocation => "Видео",
+        LocationType::MusicLocation => "Музыка",
+        LocationType::PicturesLocation => "Изображения",
+        LocationType::ApplicationsLocation => "Приложения",
+        LocationType::FontsLocation => "Шрифты",
+        LocationType::RuntimeLocation => "Среда выполнения",
+        LocationType::TempLocation => "Временный каталог",
+        LocationType::GenericDataLocation => "Общие данные",
+        LocationType::AppDataLocation => "Данные приложения",
+        LocationType::AppLocalDataLocation => "Данные приложения",
+        LocationType::GenericCacheLocation => "Общий кэш",
+        LocationType::AppCacheLocation => "Кэш",
+        LocationType::ConfigLocation => "Конфигурация",
+        LocationType::GenericConfigLocation => "Общая конфигурация",
+        LocationType::AppConfigLocation => "Конфигурация приложения",
+        LocationType::GenericStateLocation => "Общее состояние",
+        LocationType::AppStateLocation => "Состояние приложения",
+        LocationType::TemplatesLocation => "Шаблоны",
+        LocationType::PublicShareLocation => "Общедоступные",
+        LocationType::UserExecutablesLocation => "Исполняемые файлы",
+    }
+}
+
+/// Splits the POSIX-style `locale`, e.g. `de_DE.UTF-8@euro`, into the language tag
+/// and the language alone, e.g. `de_DE` and `de`, ignoring the codeset and the modifier.
+pub(crate) fn locale_tags(locale: &str) -> [&str; 2] {
+    let tag = locale.split(['.', '@']).next().unwrap_or_default();
+    let lang = tag.split(['_', '-']).next().unwrap_or_default();
+    [tag, lang]
+}
+
+/// Returns the messages locale from the `LC_ALL`, `LC_MESSAGES` or `LANG`
+/// environment variables.
+pub(crate) fn messages_locale(env: &dyn Environment) -> Option<String> {
+    LOCALE_VARS
+        .iter()
+        .filter_map(|name| env.var(name))
+        .find(|value| !value.is_empty())
+}
+
+/// Finds the translation for the POSIX-style `locale`.
+///
+/// The full language tag is tried first and then the language alone.
+fn find_translation(locale: &str) -> Option<Translation> {
+    locale_tags(locale).iter().find_map(|code| {
+        TRANSLATIONS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(code))
+            .map(|(_, translation)| *translation)
+    })
+}
+
+impl StandardPaths {
+    /// Returns a localized human-readable name of the `location`
+    /// suitable for showing in the user interface.
+    ///
+    /// The `locale` is a POSIX-style locale name like `de` or `fr_FR.UTF-8`.
+    /// If it's [`None`] the locale is taken from the `LC_ALL`, `LC_MESSAGES`
+    /// or `LANG` environment variables. Unknown locales fall back to English.
+    ///
+    /// On Linux the user directories are named after the folders configured
+    /// in the `user-dirs.dirs` file, which are usually already localized.
+    ///
+    /// # Arguments
+    /// * `location` - location type.
+    /// * `locale` - locale name.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, MapEnvironment, StandardPaths};
+    ///
+    /// let env = MapEnvironment::new().with_var("LANG", "de_DE.UTF-8");
+    /// let sp = StandardPaths::with_environment("app", "org", env);
+    /// assert_eq!(
+    ///     sp.display_name(LocationType::AppConfigLocation, None),
+    ///     "Anwendungskonfiguration"
+    /// );
+    /// assert_eq!(
+    ///     sp.display_name(LocationType::AppConfigLocation, Some("en_US")),
+    ///     "Application Configuration"
+    /// );
+    /// assert_eq!(
+    ///     sp.display_name(LocationType::TempLocation, Some("xx")),
+    ///     "Temporary Directory"
+    /// );
+    /// ```
+    pub fn display_name(&self, location: LocationType, locale: Option<&str>) -> String {
+        #[cfg(target_os = "linux")]
+        if location.is_user_dir() {
+            if let Ok(Some(path)) = self.configured_user_dir(location) {
+                let home = self.sandboxed_environment().home_dir();
+                if home.as_ref() != Some(&path) {
+                    if let Some(name) = path.file_name() {
+                        return name.to_string_lossy().into_owned();
+                    }
+                }
+            }
+        }
+
+        let env_locale;
+        let locale = match locale {
+            Some(locale) => Some(locale),
+            _ => {
+                env_locale = messages_locale(self.environment());
+                env_locale.as_deref()
+            }
+        };
+        let translation = locale.and_then(find_translation).unwrap_or(english);
+        translation(location).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapEnvironment, MemoryFileSystem};
+
+    fn paths(env: MapEnvironment) -> StandardPaths {
+        StandardPaths::builder()
+            .environment(env.with_home("/home/user"))
+            .file_system(MemoryFileSystem::new())
+            .build()
+    }
+
+    #[test]
+    fn locale_parsing() {
+        assert_eq!(locale_tags("de_DE.UTF-8@euro"), ["de_DE", "de"]);
+        assert_eq!(locale_tags("pt-BR"), ["pt-BR", "pt"]);
+        assert_eq!(locale_tags("C"), ["C", "C"]);
+        assert_eq!(locale_tags(""), ["", ""]);
+        assert!(find_translation("FR_ca").is_some());
+        assert!(find_translation("C").is_none());
+    }
+
+    #[test]
+    fn locale_precedence() {
+        let env = MapEnvironment::new()
+            .with_var("LC_ALL", "")
+            .with_var("LC_MESSAGES", "fr_FR")
+            .with_var("LANG", "de_DE");
+        assert_eq!(messages_locale(&env).as_deref(), Some("fr_FR"));
+        assert_eq!(messages_locale(&MapEnvironment::new()), None);
+
+        let sp = paths(env);
+        assert_eq!(
+            sp.display_name(LocationType::MusicLocation, None),
+            "Musique"
+        );
+        assert_eq!(
+            sp.display_name(LocationType::MusicLocation, Some("es")),
+            "Música"
+        );
+    }
+
+    #[test]
+    fn every_location_is_translated() {
+        for (_, translation) in TRANSLATIONS {
+            for location in LocationType::all() {
+                assert!(!translation(location).is_empty());
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn configured_user_dirs() {
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home("/home/user"))
+            .file_system(MemoryFileSystem::new().with_file_content(
+                "/home/user/.config/user-dirs.dirs",
+                "XDG_MUSIC_DIR=\"$HOME/Klänge\"\nXDG_DESKTOP_DIR=\"$HOME\"\n",
+            ))
+            .build();
+        assert_eq!(
+            sp.display_name(LocationType::MusicLocation, Some("fr")),
+            "Klänge"
+        );
+        // A user directory disabled by pointing it to the home isn't named after it.
+        assert_eq!(
+            sp.display_name(LocationType::DesktopLocation, Some("de")),
+            "Schreibtisch"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{Error, LocationType, StandardPaths};
+
+/// A directory returned by [`StandardPaths::ensure_writable_location`]
+/// and [`StandardPaths::ensure_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnsuredPath {
+    /// The ensured directory.
+    path: PathBuf,
+    /// The directories created by the call.
+    created: Vec<PathBuf>,
+}
+
+impl EnsuredPath {
+    /// Returns the ensured directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the directories created by the call from the outermost one.
+    ///
+    /// The list is empty if the directory already existed.
+    pub fn created(&self) -> &[PathBuf] {
+        &self.created
+    }
+
+    /// Converts into the ensured directory.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+/// Returns the access mode the directories of the `location` are created with.
+///
+/// The configuration, the cached data, the state and the runtime files
+/// are private to the user.
+fn default_mode(location: LocationType) -> u32 {
+    match location {
+        LocationType::ConfigLocation
+        | LocationType::GenericConfigLocation
+        | LocationType::AppConfigLocation
+        | LocationType::GenericCacheLocation
+        | LocationType::AppCacheLocation
+        | LocationType::GenericStateLocation
+        | LocationType::AppStateLocation
+        | LocationType::RuntimeLocation => 0o700,
+        _ => 0o755,
+    }
+}
+
+/// Returns the `path` without the trailing `suffix` components
+/// or [`None`] if it does not end with them.
+fn strip_suffix(path: &Path, suffix: &Path) -> Option<PathBuf> {
+    if !path.ends_with(suffix) {
+        return None;
+    }
+    let mut base = path.to_path_buf();
+    for _ in suffix.components() {
+        base.pop();
+    }
+    Some(base)
+}
+
+/// Creates a single directory with the `mode`.
+fn create_dir(path: &Path, mode: u32) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    builder.mode(mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    builder.create(path)
+}
+
+impl StandardPaths {
+    /// Returns the directory where files of type `location` should be written to
+    /// creating it if it does not exist.
+    ///
+    /// See [`StandardPaths::ensure_path`] for details.
+    pub fn ensure_writable_location(&self, location: LocationType) -> Result<EnsuredPath, Error> {
+        self.ensure_path(location, "")
+    }
+
+    /// Creates the `relative` directory inside the writable `location`
+    /// with all the missing parents.
+    ///
+    /// The directories are created with the default access mode of the location:
+    /// `0700` for the configuration, cache, state and runtime locations
+    /// and `0755` for the others. The application-specific components,
+    /// like `org/app`, and the `relative` ones are never followed
+    /// if they are symbolic links.
+    ///
+    /// Returns [`Error::UnexpectedSymlink`] if one of such components is a symbolic link
+    /// or [`Error::Io`] if the `relative` path is not a relative path inside the location.
+    ///
+    /// # Arguments
+    /// * `location` - location type.
+    /// * `relative` - a relative path of the directory inside the location.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, StandardPaths};
+    ///
+    /// let root = std::env::temp_dir().join("standard_paths_doc_ensure_path");
+    /// let sp = StandardPaths::builder()
+    ///     .app_name("app")
+    ///     .org_name("org")
+    ///     .test_root(&root)
+    ///     .build();
+    /// let logs = sp.ensure_path(LocationType::AppCacheLocation, "logs").unwrap();
+    /// assert_eq!(logs.path(), root.join("cache/org/app/logs"));
+    /// assert!(logs.path().is_dir());
+    /// assert!(logs.created().contains(&root.join("cache/org/app/logs")));
+    ///
+    /// let logs = sp.ensure_path(LocationType::AppCacheLocation, "logs").unwrap();
+    /// assert!(logs.created().is_empty());
+    /// # std::fs::remove_dir_all(&root).ok();
+    /// ```
+    pub fn ensure_path<P>(&self, location: LocationType, relative: P) -> Result<EnsuredPath, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let relative = relative.as_ref();
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(Error::io(
+                location,
+                relative,
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "The path must be relative and must not leave the location",
+                ),
+            ));
+        }
+
+        // Split the location into the base directory and the application-specific part
+        let path = self.writable_location(location)?;
+        let mut suffix = PathBuf::new();
+        if location.is_app_specific() {
+            self.append_organization_and_app(&mut suffix);
+        }
+        let (base, app) = match strip_suffix(&path, &suffix) {
+            Some(base) if !suffix.as_os_str().is_empty() => (base, suffix),
+            _ => (path, PathBuf::new()),
+        };
+
+        let mode = default_mode(location);
+        let mut created = Vec::new();
+
+        // The base directory is created like with `create_dir_all`
+        let mut missing = Vec::new();
+        let mut ancestor = base.as_path();
+        while !ancestor.exists() {
+            missing.push(ancestor.to_path_buf());
+            ancestor = match ancestor.parent() {
+                Some(parent) => parent,
+                _ => break,
+            };
+        }
+        for dir in missing.into_iter().rev() {
+            match create_dir(&dir, mode) {
+                Ok(()) => created.push(dir),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(Error::io(location, dir, err)),
+            }
+        }
+
+        // The rest components must be real directories
+        let mut path = base;
+        for component in app.components().chain(relative.components()) {
+            if component == Component::CurDir {
+                continue;
+            }
+            path.push(component);
+            match create_dir(&path, mode) {
+                Ok(()) => {
+                    created.push(path.clone());
+                    continue;
+                }
+                Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+                    return Err(Error::io(location, path, err))
+                }
+                _ => {}
+            }
+            let md = fs::symlink_metadata(&path).map_err(|err| Error::io(location, &path, err))?;
+            if md.file_type().is_symlink() {
+                return Err(Error::UnexpectedSymlink { location, path });
+            }
+            if !md.is_dir() {
+                return Err(Error::io(
+                    location,
+                    path,
+                    io::Error::new(io::ErrorKind::AlreadyExists, "The path is not a directory"),
+                ));
+            }
+        }
+
+        Ok(EnsuredPath { path, created })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn paths(root: &TempDir) -> StandardPaths {
+        StandardPaths::builder()
+            .app_name("app")
+            .org_name("org")
+            .test_root(root.path())
+            .build()
+    }
+
+    #[test]
+    fn invalid_relative_paths() {
+        let root = TempDir::new();
+        let sp = paths(&root);
+        for relative in ["../x", "/x", "a/../../x"] {
+            assert!(matches!(
+                sp.ensure_path(LocationType::AppCacheLocation, relative),
+                Err(Error::Io { .. })
+            ));
+        }
+        assert!(!root.join("cache").exists());
+    }
+
+    #[test]
+    fn not_a_directory() {
+        let root = TempDir::new();
+        let sp = paths(&root);
+        sp.ensure_writable_location(LocationType::AppCacheLocation)
+            .unwrap();
+        fs::write(root.join("cache/org/app/file"), "").unwrap();
+        assert!(matches!(
+            sp.ensure_path(LocationType::AppCacheLocation, "file/x"),
+            Err(Error::Io { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn private_modes() {
+        let root = TempDir::new();
+        let sp = paths(&root);
+        let ensured = sp
+            .ensure_path(LocationType::AppConfigLocation, "a/b")
+            .unwrap();
+        assert_eq!(ensured.path(), root.join("config/org/app/a/b"));
+        assert_eq!(
+            ensured.created().last(),
+            Some(&root.join("config/org/app/a/b"))
+        );
+        for dir in ensured.created() {
+            let mode = fs::metadata(dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700, "{dir:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks_are_rejected() {
+        let root = TempDir::new();
+        let target = TempDir::new();
+        let sp = paths(&root);
+
+        fs::create_dir_all(root.join("cache")).unwrap();
+        symlink(target.path(), root.join("cache/org")).unwrap();
+        match sp.ensure_writable_location(LocationType::AppCacheLocation) {
+            Err(Error::UnexpectedSymlink { path, .. }) => assert_eq!(path, root.join("cache/org")),
+            res => panic!("unexpected result: {res:?}"),
+        }
+
+        sp.ensure_writable_location(LocationType::AppConfigLocation)
+            .unwrap();
+        symlink(target.path(), root.join("config/org/app/logs")).unwrap();
+        match sp.ensure_path(LocationType::AppConfigLocation, "logs/today") {
+            Err(Error::UnexpectedSymlink { path, .. }) => {
+                assert_eq!(path, root.join("config/org/app/logs"))
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+        assert_eq!(fs::read_dir(target.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn generic_locations_are_not_split() {
+        let root = TempDir::new();
+        let target = TempDir::new();
+        // The generic location ends with the application suffix by chance
+        symlink(target.path(), root.join("app")).unwrap();
+        let sp = StandardPaths::builder()
+            .app_name("app")
+            .override_location(LocationType::GenericDataLocation, root.join("app"))
+            .build();
+        let ensured = sp
+            .ensure_path(LocationType::GenericDataLocation, "fonts")
+            .unwrap();
+        assert_eq!(ensured.created(), [root.join("app/fonts")]);
+        assert!(target.join("fonts").is_dir());
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+/// Provides the environment the standard locations are resolved in.
+///
+/// By default [`StandardPaths`](crate::StandardPaths) reads the environment
+/// of the current process with [`ProcessEnvironment`]. A custom implementation
+/// could be passed to
+/// [`StandardPaths::with_environment`](crate::StandardPaths::with_environment)
+/// to resolve the paths "as if" they were requested in another environment.
+pub trait Environment: Send + Sync {
+    /// Returns the value of the environment variable `name`
+    /// or [`None`] if it is not set.
+    fn var_os(&self, name: &str) -> Option<OsString>;
+
+    /// Returns the user's home directory or [`None`] if it cannot be determined.
+    fn home_dir(&self) -> Option<PathBuf>;
+
+    /// Returns the directory for temporary files.
+    fn temp_dir(&self) -> PathBuf;
+
+    /// Returns the effective user id or [`None`] if the platform
+    /// has no concept of user ids.
+    fn uid(&self) -> Option<u32>;
+
+    /// Returns the name of the current user.
+    fn user_name(&self) -> io::Result<String>;
+
+    /// Returns the value of the environment variable `name`
+    /// or [`None`] if it is not set or is not valid unicode.
+    fn var(&self, name: &str) -> Option<String> {
+        self.var_os(name).and_then(|value| value.into_string().ok())
+    }
+}
+
+/// The [`Environment`] of the current process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessEnvironment;
+
+impl Environment for ProcessEnvironment {
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        env::var_os(name)
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        home::home_dir()
+    }
+
+    fn temp_dir(&self) -> PathBuf {
+        env::temp_dir()
+    }
+
+    #[cfg(unix)]
+    fn uid(&self) -> Option<u32> {
+        Some(nix::unistd::geteuid().as_raw())
+    }
+
+    #[cfg(not(unix))]
+    fn uid(&self) -> Option<u32> {
+        None
+    }
+
+    #[cfg(unix)]
+    fn user_name(&self) -> io::Result<String> {
+        let uid = nix::unistd::geteuid();
+        match nix::unistd::User::from_uid(uid) {
+            Ok(Some(user)) => Ok(user.name),
+            Ok(None) => Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no user with uid {uid}"),
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn user_name(&self) -> io::Result<String> {
+        env::var("USERNAME").map_err(|err| io::Error::new(ErrorKind::NotFound, err))
+    }
+}
+
+/// An in-memory [`Environment`].
+///
+/// Is useful for tests which should not depend on the environment
+/// of the current process and for resolving paths in a different environment.
+///
+/// If no home or temporary directory is set explicitly,
+/// they are taken from the `HOME` and `TMPDIR` variables.
+///
+/// ### Example
+/// ```
+/// use standard_paths::{LocationType, MapEnvironment, StandardPaths};
+/// use std::path::PathBuf;
+///
+/// let env = MapEnvironment::new()
+///     .with_home("/home/user")
+///     .with_var("XDG_CONFIG_HOME", "/etc/user");
+/// let sp = StandardPaths::with_environment("app", "org", env);
+/// assert_eq!(
+///     sp.writable_location(LocationType::AppConfigLocation).unwrap(),
+///     PathBuf::from("/etc/user/org/app")
+/// );
+/// assert_eq!(
+///     sp.writable_location(LocationType::GenericCacheLocation).unwrap(),
+///     PathBuf::from("/home/user/.cache")
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MapEnvironment {
+    /// Environment variables.
+    vars: HashMap<String, OsString>,
+    /// Home directory.
+    home: Option<PathBuf>,
+    /// Temporary files directory.
+    temp: Option<PathBuf>,
+    /// Effective user id.
+    uid: Option<u32>,
+    /// User name.
+    user_name: Option<String>,
+}
+
+impl MapEnvironment {
+    /// Constructs a new empty [`MapEnvironment`].
+    pub fn new() -> MapEnvironment {
+        Default::default()
+    }
+
+    /// Sets the environment variable `name` to `value`.
+    pub fn with_var<S, V>(mut self, name: S, value: V) -> MapEnvironment
+    where
+        S: Into<String>,
+        V: Into<OsString>,
+    {
+        self.set_var(name, value);
+        self
+    }
+
+    /// Sets the user's home directory.
+    pub fn with_home<P>(mut self, path: P) -> MapEnvironment
+    where
+        P: Into<PathBuf>,
+    {
+        self.home = Some(path.into());
+        self
+    }
+
+    /// Sets the directory for temporary files.
+    pub fn with_temp_dir<P>(mut self, path: P) -> MapEnvironment
+    where
+        P: Into<PathBuf>,
+    {
+        self.temp = Some(path.into());
+        self
+    }
+
+    /// Sets the effective user id.
+    pub fn with_uid(mut self, uid: u32) -> MapEnvironment {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Sets the name of the current user.
+    pub fn with_user_name<S>(mut self, name: S) -> MapEnvironment
+    where
+        S: Into<String>,
+    {
+        self.user_name = Some(name.into());
+        self
+    }
+
+    /// Sets the environment variable `name` to `value`.
+    pub fn set_var<S, V>(&mut self, name: S, value: V)
+    where
+        S: Into<String>,
+        V: Into<OsString>,
+    {
+        self.vars.insert(name.into(), value.into());
+    }
+
+    /// Removes the environment variable `name`.
+    pub fn remove_var(&mut self, name: &str) {
+        self.vars.remove(name);
+    }
+}
+
+impl Environment for MapEnvironment {
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        self.vars.get(name).cloned()
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        self.home
+            .clone()
+            .or_else(|| self.var_os("HOME").map(PathBuf::from))
+    }
+
+    fn temp_dir(&self) -> PathBuf {
+        self.temp
+            .clone()
+            .or_else(|| self.var_os("TMPDIR").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+    }
+
+    fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    fn user_name(&self) -> io::Result<String> {
+        self.user_name
+            .clone()
+            .or_else(|| self.var("USER"))
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "user name is not set"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_environment_fallbacks() {
+        let env = MapEnvironment::new();
+        assert_eq!(env.home_dir(), None);
+        assert_eq!(env.temp_dir(), PathBuf::from("/tmp"));
+        assert_eq!(env.uid(), None);
+        assert_eq!(env.user_name().unwrap_err().kind(), ErrorKind::NotFound);
+
+        let env = MapEnvironment::new()
+            .with_var("HOME", "/home/var")
+            .with_var("TMPDIR", "/var/tmp")
+            .with_var("USER", "var");
+        assert_eq!(env.home_dir(), Some(PathBuf::from("/home/var")));
+        assert_eq!(env.temp_dir(), PathBuf::from("/var/tmp"));
+        assert_eq!(env.user_name().unwrap(), "var");
+
+        let env = env
+            .with_home("/home/user")
+            .with_temp_dir("/tmp/user")
+            .with_user_name("user")
+            .with_uid(1000);
+        assert_eq!(env.home_dir(), Some(PathBuf::from("/home/user")));
+        assert_eq!(env.temp_dir(), PathBuf::from("/tmp/user"));
+        assert_eq!(env.user_name().unwrap(), "user");
+        assert_eq!(env.uid(), Some(1000));
+    }
+
+    #[test]
+    fn map_environment_vars() {
+        let mut env = MapEnvironment::new().with_var("A", "1");
+        env.set_var("B", "2");
+        assert_eq!(env.var("A").as_deref(), Some("1"));
+        assert_eq!(env.var_os("B"), Some(OsString::from("2")));
+        env.remove_var("A");
+        assert_eq!(env.var("A"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_unicode_var() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let env = MapEnvironment::new().with_var("A", OsString::from_vec(vec![0xff]));
+        assert!(env.var_os("A").is_some());
+        assert_eq!(env.var("A"), None);
+    }
+}
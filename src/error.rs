@@ -0,0 +1,368 @@
+use std::{
+    error, fmt,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{probe::Rejection, LocationType};
+
+/// Describes why a runtime directory is considered insecure.
+///
+/// Is used by the [`Error::InsecureRuntimeDir`] variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RuntimeDirIssue {
+    /// The directory is owned by another user.
+    WrongOwner {
+        /// The actual owner of the directory.
+        owner: u32,
+        /// The effective user id of the current process.
+        expected: u32,
+    },
+    /// The directory is a symbolic link.
+    Symlink,
+    /// The path exists but it's not a directory.
+    NotADirectory,
+    /// The directory is accessible by the other users and its permissions
+    /// cannot be corrected.
+    BadPermissions {
+        /// The actual access mode of the directory.
+        mode: u32,
+    },
+}
+
+impl fmt::Display for RuntimeDirIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeDirIssue::WrongOwner { owner, expected } => {
+                write!(f, "wrong ownership - {owner} instead of {expected}")
+            }
+            RuntimeDirIssue::Symlink => write!(f, "it's a symbolic link"),
+            RuntimeDirIssue::NotADirectory => write!(f, "it's not a directory"),
+            RuntimeDirIssue::BadPermissions { mode } => {
+                write!(f, "wrong permissions - {mode:04o} instead of 0700")
+            }
+        }
+    }
+}
+
+/// Enumerates the errors returned by the crate methods.
+///
+/// Every variant that relates to a particular location carries its [`LocationType`]
+/// and, when available, the offending path.
+///
+/// The error could be converted into [`std::io::Error`] for compatibility
+/// with the code written for the previous versions of the crate.
+///
+/// ### Example
+/// ```
+/// use standard_paths::{Error, LocationType, StandardPaths};
+///
+/// let sp = StandardPaths::new("app", "org");
+/// match sp.writable_location(LocationType::AppConfigLocation) {
+///     Ok(path) => println!("{path:?}"),
+///     Err(Error::HomeNotFound { .. }) => eprintln!("HOME is not set"),
+///     Err(err) => eprintln!("{err}"),
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The user's home directory cannot be determined.
+    HomeNotFound {
+        /// The requested location type.
+        location: LocationType,
+    },
+    /// The current user cannot be determined.
+    UserNotFound {
+        /// The requested location type.
+        location: LocationType,
+        /// The description of the failure.
+        reason: String,
+    },
+    /// An environment variable contains an invalid value.
+    InvalidEnvVar {
+        /// The requested location type.
+        location: LocationType,
+        /// The variable name.
+        name: String,
+        /// The variable value.
+        value: PathBuf,
+        /// The description of the problem.
+        reason: String,
+    },
+    /// The runtime directory does not meet the security requirements.
+    InsecureRuntimeDir {
+        /// The runtime directory path.
+        path: PathBuf,
+        /// The detected problem.
+        issue: RuntimeDirIssue,
+    },
+    /// The `user-dirs.dirs` file cannot be parsed.
+    UserDirsParse {
+        /// The file path.
+        path: PathBuf,
+        /// The line number starting from 1.
+        line: usize,
+        /// The description of the problem.
+        reason: String,
+    },
+    /// A path component which must not be followed is a symbolic link.
+    UnexpectedSymlink {
+        /// The requested location type.
+        location: LocationType,
+        /// The symbolic link path.
+        path: PathBuf,
+    },
+    /// None of the candidates probed by
+    /// [`StandardPaths::probe_writable_location`](crate::StandardPaths::probe_writable_location)
+    /// is writable.
+    NoWritableLocation {
+        /// The requested location type.
+        location: LocationType,
+        /// The rejected candidates in the probing order.
+        rejected: Vec<Rejection>,
+    },
+    /// A glob pattern is malformed.
+    InvalidGlob {
+        /// The pattern.
+        pattern: String,
+        /// The description of the problem.
+        reason: String,
+    },
+    /// The location type is not supported on the current platform.
+    UnsupportedLocation(LocationType),
+    /// An I/O operation failed.
+    Io {
+        /// The requested location type if any.
+        location: Option<LocationType>,
+        /// The path the operation was performed on if any.
+        path: Option<PathBuf>,
+        /// The underlying error.
+        source: io::Error,
+    },
+}
+
+impl Error {
+    /// Constructs a new [`Error::Io`] for the `path` accessed while resolving the `location`.
+    pub(crate) fn io<P>(location: LocationType, path: P, source: io::Error) -> Error
+    where
+        P: Into<PathBuf>,
+    {
+        Error::Io {
+            location: Some(location),
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    /// Returns the location type the error relates to if any.
+    pub fn location(&self) -> Option<LocationType> {
+        match self {
+            Error::HomeNotFound { location }
+            | Error::UserNotFound { location, .. }
+            | Error::InvalidEnvVar { location, .. }
+            | Error::UnexpectedSymlink { location, .. }
+            | Error::NoWritableLocation { location, .. }
+            | Error::UnsupportedLocation(location) => Some(*location),
+            Error::InsecureRuntimeDir { .. } => Some(LocationType::RuntimeLocation),
+            Error::Io { location, .. } => *location,
+            Error::UserDirsParse { .. } | Error::InvalidGlob { .. } => None,
+        }
+    }
+
+    /// Returns the offending path if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::InvalidEnvVar { value, .. } => Some(value),
+            Error::InsecureRuntimeDir { path, .. }
+            | Error::UserDirsParse { path, .. }
+            | Error::UnexpectedSymlink { path, .. } => Some(path),
+            Error::Io { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`std::io::ErrorKind`] the error is mapped to
+    /// when converted into [`std::io::Error`].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::HomeNotFound { .. } | Error::UserNotFound { .. } => ErrorKind::NotFound,
+            Error::InvalidEnvVar { .. } | Error::InvalidGlob { .. } => ErrorKind::InvalidInput,
+            Error::InsecureRuntimeDir { .. }
+            | Error::UnexpectedSymlink { .. }
+            | Error::NoWritableLocation { .. } => ErrorKind::PermissionDenied,
+            Error::UserDirsParse { .. } => ErrorKind::InvalidData,
+            Error::UnsupportedLocation(_) => ErrorKind::Unsupported,
+            Error::Io { source, .. } => source.kind(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::HomeNotFound { location } => {
+                write!(f, "Error getting HOME directory for {location:?}")
+            }
+            Error::UserNotFound { location, reason } => {
+                write!(
+                    f,
+                    "Failed to detect current user for {location:?}: {reason}"
+                )
+            }
+            Error::InvalidEnvVar {
+                name,
+                value,
+                reason,
+                ..
+            } => write!(f, "'{name}' points to '{}' {reason}", value.display()),
+            Error::InsecureRuntimeDir { path, issue } => {
+                write!(
+                    f,
+                    "Insecure runtime directory '{}': {issue}",
+                    path.display()
+                )
+            }
+            Error::UserDirsParse { path, line, reason } => {
+                write!(f, "{}:{line}: {reason}", path.display())
+            }
+            Error::UnexpectedSymlink { path, .. } => {
+                write!(f, "'{}' is a symbolic link", path.display())
+            }
+            Error::NoWritableLocation { location, rejected } => {
+                write!(f, "No writable directory found for {location:?}")?;
+                for rejection in rejected {
+                    write!(f, "; {}", rejection.error())?;
+                }
+                Ok(())
+            }
+            Error::InvalidGlob { pattern, reason } => {
+                write!(f, "Invalid glob pattern '{pattern}': {reason}")
+            }
+            Error::UnsupportedLocation(location) => {
+                write!(f, "{location:?} is not supported on this platform")
+            }
+            Error::Io {
+                path: Some(path),
+                source,
+                ..
+            } => write!(f, "'{}': {source}", path.display()),
+            Error::Io { source, .. } => source.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        io::Error::new(err.kind(), err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn accessors() {
+        let err = Error::HomeNotFound {
+            location: LocationType::AppConfigLocation,
+        };
+        assert_eq!(err.location(), Some(LocationType::AppConfigLocation));
+        assert_eq!(err.path(), None);
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let err = Error::InsecureRuntimeDir {
+            path: "/run/user/1000".into(),
+            issue: RuntimeDirIssue::BadPermissions { mode: 0o755 },
+        };
+        assert_eq!(err.location(), Some(LocationType::RuntimeLocation));
+        assert_eq!(err.path(), Some(Path::new("/run/user/1000")));
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+        let err = Error::UserDirsParse {
+            path: "user-dirs.dirs".into(),
+            line: 3,
+            reason: "trailing backslash".into(),
+        };
+        assert_eq!(err.location(), None);
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = Error::UnsupportedLocation(LocationType::FontsLocation);
+        assert_eq!(err.location(), Some(LocationType::FontsLocation));
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn display() {
+        let err = Error::InvalidEnvVar {
+            location: LocationType::GenericConfigLocation,
+            name: "XDG_CONFIG_HOME".into(),
+            value: "config".into(),
+            reason: "which is not absolute".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "'XDG_CONFIG_HOME' points to 'config' which is not absolute"
+        );
+        let err = Error::InsecureRuntimeDir {
+            path: "/tmp/runtime-user".into(),
+            issue: RuntimeDirIssue::WrongOwner {
+                owner: 0,
+                expected: 1000,
+            },
+        };
+        assert_eq!(
+            err.to_string(),
+            "Insecure runtime directory '/tmp/runtime-user': wrong ownership - 0 instead of 1000"
+        );
+        assert_eq!(
+            RuntimeDirIssue::BadPermissions { mode: 0o755 }.to_string(),
+            "wrong permissions - 0755 instead of 0700"
+        );
+        let err = Error::NoWritableLocation {
+            location: LocationType::AppCacheLocation,
+            rejected: Vec::new(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "No writable directory found for AppCacheLocation"
+        );
+    }
+
+    #[test]
+    fn io_errors() {
+        let err = Error::io(
+            LocationType::AppDataLocation,
+            "/data",
+            io::Error::new(ErrorKind::PermissionDenied, "denied"),
+        );
+        assert_eq!(err.location(), Some(LocationType::AppDataLocation));
+        assert_eq!(err.path(), Some(Path::new("/data")));
+        assert_eq!(err.to_string(), "'/data': denied");
+        assert!(err.source().is_some());
+
+        let err = io::Error::from(err);
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert!(err.get_ref().unwrap().is::<Error>());
+
+        let err = Error::Io {
+            location: None,
+            path: None,
+            source: io::Error::other("failed"),
+        };
+        assert_eq!(err.to_string(), "failed");
+        assert_eq!(err.location(), None);
+    }
+}
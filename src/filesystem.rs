@@ -0,0 +1,852 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    ops::Bound,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        PoisonError, RwLock, RwLockReadGuard,
+    },
+};
+
+/// Enumerates the kinds of filesystem entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    /// A regular file.
+    File,
+    /// A directory.
+    Directory,
+    /// A symbolic link.
+    Symlink,
+    /// Any other entry (a socket, a device, etc.).
+    Other,
+}
+
+/// Metadata of a filesystem entry as seen by a [`FileSystem`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Entry kind.
+    kind: FileKind,
+    /// Unix permission bits.
+    mode: u32,
+    /// Owner user id.
+    uid: Option<u32>,
+    /// Size in bytes.
+    len: u64,
+}
+
+impl Metadata {
+    /// Constructs a new [`Metadata`] of the `kind` with the Unix permission bits `mode`.
+    pub fn new(kind: FileKind, mode: u32) -> Metadata {
+        Metadata {
+            kind,
+            mode: mode & 0o7777,
+            uid: None,
+            len: 0,
+        }
+    }
+
+    /// Sets the owner user id.
+    pub fn with_uid(mut self, uid: u32) -> Metadata {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Sets the entry size in bytes.
+    pub fn with_len(mut self, len: u64) -> Metadata {
+        self.len = len;
+        self
+    }
+
+    /// Returns the entry kind.
+    pub fn kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// Returns `true` if the entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.kind == FileKind::File
+    }
+
+    /// Returns `true` if the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Directory
+    }
+
+    /// Returns `true` if the entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.kind == FileKind::Symlink
+    }
+
+    /// Returns the Unix permission bits.
+    ///
+    /// On platforms without Unix permissions the bits are emulated
+    /// from the read-only flag.
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// Returns the owner user id or [`None`] if it's unknown.
+    pub fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// Returns the entry size in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the entry size is zero.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl From<fs::Metadata> for Metadata {
+    fn from(md: fs::Metadata) -> Metadata {
+        let file_type = md.file_type();
+        let kind = if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Directory
+        } else if file_type.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+        #[cfg(unix)]
+        let res = {
+            use std::os::unix::fs::MetadataExt;
+            Metadata::new(kind, md.mode()).with_uid(md.uid())
+        };
+        #[cfg(not(unix))]
+        let res = {
+            let mode = if md.permissions().readonly() {
+                0o555
+            } else {
+                0o777
+            };
+            Metadata::new(kind, mode)
+        };
+        res.with_len(md.len())
+    }
+}
+
+/// Provides the filesystem queries used to locate files and executables,
+/// to read the xdg-user-dirs configuration and to prepare the test mode sandbox.
+///
+/// By default [`StandardPaths`](crate::StandardPaths) queries the real filesystem
+/// with [`StdFileSystem`]. Another implementation could be set with
+/// [`StandardPaths::set_file_system`](crate::StandardPaths::set_file_system)
+/// to run the lookups against an in-memory tree, an archive or a chroot-like root.
+pub trait FileSystem: Send + Sync {
+    /// Returns the metadata of the entry at `path` traversing symbolic links.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Returns the metadata of the entry at `path` without traversing symbolic links.
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Returns the canonical absolute form of `path` with all intermediate
+    /// components normalized and symbolic links resolved.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns the names of the entries of the directory at `path` sorted by name.
+    ///
+    /// The default implementation returns an [`ErrorKind::Unsupported`] error,
+    /// so the lookups which list directories find nothing.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let _ = path;
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "listing directories is not supported",
+        ))
+    }
+
+    /// Reads the whole content of the file at `path`.
+    ///
+    /// The default implementation returns an [`ErrorKind::Unsupported`] error,
+    /// so the configuration files, like `user-dirs.dirs`, are treated as unreadable.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let _ = path;
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "reading files is not supported",
+        ))
+    }
+
+    /// Creates the directory at `path` with all the missing parents.
+    ///
+    /// The default implementation returns an [`ErrorKind::Unsupported`] error.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let _ = path;
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "creating directories is not supported",
+        ))
+    }
+
+    /// Writes the `content` to the file at `path` replacing the existing one.
+    ///
+    /// The default implementation returns an [`ErrorKind::Unsupported`] error.
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let _ = (path, content);
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "writing files is not supported",
+        ))
+    }
+
+    /// Replaces the content of the file at `path` so the readers see either
+    /// the old or the new content, keeping the permission bits of the existing file.
+    ///
+    /// The default implementation calls [`FileSystem::write`].
+    fn write_atomic(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        self.write(path, content)
+    }
+
+    /// Returns `true` if `path` points to an existing entry.
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Returns `true` if `path` points to a regular file.
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).map(|md| md.is_file()).unwrap_or(false)
+    }
+
+    /// Returns `true` if `path` points to a directory.
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).map(|md| md.is_dir()).unwrap_or(false)
+    }
+
+    /// Returns `true` if the entry at `path` could be read by the current user.
+    ///
+    /// The default implementation checks the read permission bits.
+    fn is_readable(&self, path: &Path) -> bool {
+        self.metadata(path)
+            .map(|md| md.mode() & 0o444 != 0)
+            .unwrap_or(false)
+    }
+
+    /// Returns `true` if the entry at `path` could be modified by the current user.
+    ///
+    /// The default implementation checks the write permission bits.
+    fn is_writable(&self, path: &Path) -> bool {
+        self.metadata(path)
+            .map(|md| md.mode() & 0o222 != 0)
+            .unwrap_or(false)
+    }
+
+    /// Returns `true` if `path` points to an executable.
+    ///
+    /// The default implementation checks the executable permission bits.
+    fn is_executable(&self, path: &Path) -> bool {
+        match self.metadata(path) {
+            Ok(md) => md.is_file() && md.mode() & 0o111 != 0,
+            _ => false,
+        }
+    }
+}
+
+/// The [`FileSystem`] backed by [`std::fs`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::metadata(path).map(Metadata::from)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::symlink_metadata(path).map(Metadata::from)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let mut names = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        fs::write(path, content)
+    }
+
+    fn write_atomic(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        // The content is written to a temporary file in the same directory first
+        // which then replaces the original file.
+        let permissions = fs::metadata(path).ok().map(|md| md.permissions());
+        let (tmp, mut file) = create_temp_file(path)?;
+        let res = file
+            .write_all(content)
+            .and_then(|_| match permissions {
+                Some(permissions) => file.set_permissions(permissions),
+                _ => Ok(()),
+            })
+            .and_then(|_| file.sync_all())
+            .and_then(|_| {
+                drop(file);
+                fs::rename(&tmp, path)
+            });
+        if res.is_err() {
+            fs::remove_file(&tmp).ok();
+        }
+        res
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_readable(&self, path: &Path) -> bool {
+        crate::linux::has_access(path, nix::libc::R_OK)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_writable(&self, path: &Path) -> bool {
+        crate::linux::has_access(path, nix::libc::W_OK)
+    }
+
+    #[cfg(windows)]
+    fn is_executable(&self, path: &Path) -> bool {
+        crate::windows::is_executable(path)
+    }
+}
+
+/// The maximum number of attempts to create a unique temporary file.
+const MAX_TEMP_ATTEMPTS: usize = 100;
+
+/// Creates a new temporary file next to `path` named after it.
+///
+/// The file is created exclusively, so an existing file or a symbolic link
+/// planted under the same name is never opened.
+fn create_temp_file(path: &Path) -> io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().unwrap_or_default();
+    let mut attempts = 0;
+    loop {
+        let mut tmp_name = name.to_os_string();
+        tmp_name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp = path.with_file_name(tmp_name);
+        match File::options().write(true).create_new(true).open(&tmp) {
+            Ok(file) => return Ok((tmp, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                attempts += 1;
+                if attempts == MAX_TEMP_ATTEMPTS {
+                    return Err(err);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// An entry of the [`MemoryFileSystem`].
+#[derive(Debug, Clone)]
+enum Node {
+    File { mode: u32, content: Vec<u8> },
+    Directory { mode: u32 },
+    Symlink { target: PathBuf },
+}
+
+/// Maximum number of symbolic links traversed while resolving a path.
+const MAX_SYMLINKS: usize = 40;
+
+/// An in-memory [`FileSystem`].
+///
+/// The tree is filled declaratively. Parent directories of the added entries
+/// are created automatically. The files and the directories could also be added
+/// through the [`FileSystem`] write methods, e.g. by the test mode.
+///
+/// ### Example
+/// ```
+/// use standard_paths::{FileSystem, MemoryFileSystem};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("/usr/share/app/data.bin")
+///     .with_executable("/usr/bin/tool")
+///     .with_symlink("/usr/local/bin/tool", "/usr/bin/tool");
+/// assert!(fs.is_dir(Path::new("/usr/share/app")));
+/// assert!(fs.is_executable(Path::new("/usr/local/bin/tool")));
+/// assert!(!fs.is_executable(Path::new("/usr/share/app/data.bin")));
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    /// The entries, are modified through the [`FileSystem`] write methods.
+    tree: RwLock<Tree>,
+}
+
+/// The entries of the [`MemoryFileSystem`] by their normalized absolute paths.
+#[derive(Debug, Clone, Default)]
+struct Tree(BTreeMap<PathBuf, Node>);
+
+impl Clone for MemoryFileSystem {
+    fn clone(&self) -> MemoryFileSystem {
+        MemoryFileSystem {
+            tree: RwLock::new(self.tree().clone()),
+        }
+    }
+}
+
+impl MemoryFileSystem {
+    /// Constructs a new empty [`MemoryFileSystem`].
+    pub fn new() -> MemoryFileSystem {
+        Default::default()
+    }
+
+    /// Adds an empty regular file with the `0644` mode.
+    pub fn with_file<P>(self, path: P) -> MemoryFileSystem
+    where
+        P: AsRef<Path>,
+    {
+        self.with_file_content(path, Vec::new())
+    }
+
+    /// Adds a regular file with the `content` and the `0644` mode.
+    pub fn with_file_content<P, C>(mut self, path: P, content: C) -> MemoryFileSystem
+    where
+        P: AsRef<Path>,
+        C: Into<Vec<u8>>,
+    {
+        self.tree_mut().insert(
+            path.as_ref(),
+            Node::File {
+                mode: 0o644,
+                content: content.into(),
+            },
+        );
+        self
+    }
+
+    /// Adds a regular file with the `0755` mode.
+    pub fn with_executable<P>(mut self, path: P) -> MemoryFileSystem
+    where
+        P: AsRef<Path>,
+    {
+        self.tree_mut().insert(
+            path.as_ref(),
+            Node::File {
+                mode: 0o755,
+                content: Vec::new(),
+            },
+        );
+        self
+    }
+
+    /// Adds a directory with the `0755` mode.
+    pub fn with_dir<P>(mut self, path: P) -> MemoryFileSystem
+    where
+        P: AsRef<Path>,
+    {
+        self.tree_mut()
+            .insert(path.as_ref(), Node::Directory { mode: 0o755 });
+        self
+    }
+
+    /// Adds a symbolic link at `path` pointing to `target`.
+    ///
+    /// A relative `target` is resolved against the parent of `path`.
+    pub fn with_symlink<P, T>(mut self, path: P, target: T) -> MemoryFileSystem
+    where
+        P: AsRef<Path>,
+        T: Into<PathBuf>,
+    {
+        self.tree_mut().insert(
+            path.as_ref(),
+            Node::Symlink {
+                target: target.into(),
+            },
+        );
+        self
+    }
+
+    /// Changes the permission bits of the file or directory at `path`.
+    ///
+    /// Does nothing if there is no such entry or it is a symbolic link.
+    pub fn with_mode<P>(mut self, path: P, mode: u32) -> MemoryFileSystem
+    where
+        P: AsRef<Path>,
+    {
+        match self.tree_mut().0.get_mut(&normalize(path.as_ref())) {
+            Some(Node::File { mode: m, .. }) | Some(Node::Directory { mode: m }) => *m = mode,
+            _ => {}
+        }
+        self
+    }
+
+    fn tree(&self) -> RwLockReadGuard<'_, Tree> {
+        self.tree.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn tree_mut(&mut self) -> &mut Tree {
+        self.tree.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn node_metadata(node: &Node) -> Metadata {
+        match node {
+            Node::File { mode, content } => {
+                Metadata::new(FileKind::File, *mode).with_len(content.len() as u64)
+            }
+            Node::Directory { mode } => Metadata::new(FileKind::Directory, *mode),
+            Node::Symlink { .. } => Metadata::new(FileKind::Symlink, 0o777),
+        }
+    }
+}
+
+impl Tree {
+    fn insert(&mut self, path: &Path, node: Node) {
+        let path = normalize(path);
+        let mut parent = path.parent();
+        while let Some(dir) = parent {
+            self.0
+                .entry(dir.to_path_buf())
+                .or_insert(Node::Directory { mode: 0o755 });
+            parent = dir.parent();
+        }
+        self.0.insert(path, node);
+    }
+
+    /// Resolves all the symbolic links in `path`.
+    ///
+    /// If `follow_last` is `false` the last component is not resolved.
+    fn resolve(&self, path: &Path, follow_last: bool) -> io::Result<PathBuf> {
+        let mut pending: Vec<PathBuf> = components(path);
+        let mut res = PathBuf::new();
+        let mut links = 0;
+        while let Some(component) = pending.pop() {
+            match component.components().next() {
+                Some(Component::ParentDir) => {
+                    res.pop();
+                    continue;
+                }
+                Some(Component::CurDir) | None => continue,
+                _ => res.push(component),
+            }
+            if !follow_last && pending.is_empty() {
+                break;
+            }
+            if let Some(Node::Symlink { target }) = self.0.get(&res) {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "too many levels of symbolic links",
+                    ));
+                }
+                res.pop();
+                pending.extend(components(target));
+            }
+        }
+        Ok(res)
+    }
+
+    fn lookup(&self, path: &Path) -> io::Result<&Node> {
+        self.0
+            .get(path)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no such file or directory"))
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let tree = self.tree();
+        let path = tree.resolve(path, true)?;
+        tree.lookup(&path).map(MemoryFileSystem::node_metadata)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let tree = self.tree();
+        let path = tree.resolve(path, false)?;
+        tree.lookup(&path).map(MemoryFileSystem::node_metadata)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let tree = self.tree();
+        let path = tree.resolve(path, true)?;
+        tree.lookup(&path)?;
+        Ok(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let tree = self.tree();
+        let path = tree.resolve(path, true)?;
+        match tree.lookup(&path)? {
+            Node::Directory { .. } => {}
+            _ => return Err(io::Error::new(ErrorKind::NotADirectory, "not a directory")),
+        }
+        // The descendants of a directory follow it in the map
+        Ok(tree
+            .0
+            .range::<PathBuf, _>((Bound::Excluded(&path), Bound::Unbounded))
+            .take_while(|(entry, _)| entry.starts_with(&path))
+            .filter(|(entry, _)| entry.parent() == Some(path.as_path()))
+            .filter_map(|(entry, _)| entry.file_name().map(OsString::from))
+            .collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let tree = self.tree();
+        let path = tree.resolve(path, true)?;
+        match tree.lookup(&path)? {
+            Node::File { content, .. } => Ok(content.clone()),
+            _ => Err(io::Error::new(ErrorKind::IsADirectory, "is a directory")),
+        }
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut tree = self.tree.write().unwrap_or_else(PoisonError::into_inner);
+        let path = tree.resolve(path, true)?;
+        for dir in path.ancestors() {
+            match tree.0.get(dir) {
+                Some(Node::Directory { .. }) => break,
+                Some(_) => return Err(io::Error::new(ErrorKind::NotADirectory, "not a directory")),
+                _ => {}
+            }
+        }
+        tree.insert(&path, Node::Directory { mode: 0o755 });
+        Ok(())
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let mut tree = self.tree.write().unwrap_or_else(PoisonError::into_inner);
+        let path = tree.resolve(path, true)?;
+        match path
+            .parent()
+            .map(|parent| tree.lookup(parent))
+            .transpose()?
+        {
+            Some(Node::Directory { .. }) | None => {}
+            _ => return Err(io::Error::new(ErrorKind::NotADirectory, "not a directory")),
+        }
+        let mode = match tree.0.get(&path) {
+            Some(Node::File { mode, .. }) => *mode,
+            Some(_) => return Err(io::Error::new(ErrorKind::IsADirectory, "is a directory")),
+            _ => 0o644,
+        };
+        tree.insert(
+            &path,
+            Node::File {
+                mode,
+                content: content.into(),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Splits `path` into owned components in the reverse order.
+fn components(path: &Path) -> Vec<PathBuf> {
+    path.components()
+        .rev()
+        .map(|c| PathBuf::from(c.as_os_str()))
+        .collect()
+}
+
+/// Lexically normalizes `path` removing `.` and `..` components.
+fn normalize(path: &Path) -> PathBuf {
+    let mut res = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                res.pop();
+            }
+            Component::CurDir => {}
+            _ => res.push(component),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fs() -> MemoryFileSystem {
+        MemoryFileSystem::new()
+            .with_file_content("/etc/app.conf", "key=value")
+            .with_executable("/usr/bin/tool")
+            .with_dir("/var/empty")
+            .with_symlink("/usr/local/bin/tool", "../../bin/tool")
+            .with_symlink("/loop/a", "/loop/b")
+            .with_symlink("/loop/b", "/loop/a")
+            .with_mode("/etc/app.conf", 0o600)
+    }
+
+    #[test]
+    fn metadata() {
+        let fs = fs();
+        let md = fs.metadata(Path::new("/etc/app.conf")).unwrap();
+        assert!(md.is_file());
+        assert_eq!(md.mode(), 0o600);
+        assert_eq!(md.len(), 9);
+        assert_eq!(md.uid(), None);
+        assert!(fs.is_dir(Path::new("/usr/local")));
+        assert!(fs.is_executable(Path::new("/usr/local/bin/tool")));
+        assert!(!fs.is_executable(Path::new("/usr/local/bin")));
+        assert!(fs
+            .symlink_metadata(Path::new("/usr/local/bin/tool"))
+            .unwrap()
+            .is_symlink());
+        assert_eq!(
+            fs.metadata(Path::new("/missing")).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            fs.metadata(Path::new("/loop/a")).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn canonicalize() {
+        let fs = fs();
+        assert_eq!(
+            fs.canonicalize(Path::new("/usr/local/bin/./tool")).unwrap(),
+            Path::new("/usr/bin/tool")
+        );
+        assert_eq!(
+            fs.canonicalize(Path::new("/usr/local/../bin")).unwrap(),
+            Path::new("/usr/bin")
+        );
+        assert!(fs.canonicalize(Path::new("/usr/missing")).is_err());
+    }
+
+    #[test]
+    fn read() {
+        let fs = fs();
+        assert_eq!(fs.read(Path::new("/etc/app.conf")).unwrap(), b"key=value");
+        assert_eq!(fs.read(Path::new("/usr/local/bin/tool")).unwrap(), b"");
+        assert!(fs.read(Path::new("/etc")).is_err());
+        assert!(fs.read(Path::new("/missing")).is_err());
+    }
+
+    #[test]
+    fn read_dir() {
+        let fs = fs();
+        assert_eq!(
+            fs.read_dir(Path::new("/usr")).unwrap(),
+            [OsString::from("bin"), OsString::from("local")]
+        );
+        assert!(fs.read_dir(Path::new("/var/empty")).unwrap().is_empty());
+        assert_eq!(
+            fs.read_dir(Path::new("/etc/app.conf")).unwrap_err().kind(),
+            ErrorKind::NotADirectory
+        );
+    }
+
+    #[test]
+    fn default_methods() {
+        struct Minimal;
+
+        impl FileSystem for Minimal {
+            fn metadata(&self, _: &Path) -> io::Result<Metadata> {
+                Ok(Metadata::new(FileKind::File, 0o400))
+            }
+
+            fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+                self.metadata(path)
+            }
+
+            fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+                Ok(path.into())
+            }
+        }
+
+        let path = Path::new("/file");
+        assert!(Minimal.is_file(path));
+        assert!(Minimal.is_readable(path));
+        assert!(!Minimal.is_writable(path));
+        assert!(!Minimal.is_executable(path));
+        assert_eq!(
+            Minimal.read(path).unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(
+            Minimal.read_dir(path).unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn write() {
+        let fs = fs();
+        fs.create_dir_all(Path::new("/usr/local/bin/new/dir"))
+            .unwrap();
+        assert!(fs.is_dir(Path::new("/usr/local/bin/new/dir")));
+        assert_eq!(
+            fs.create_dir_all(Path::new("/etc/app.conf/dir"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotADirectory
+        );
+
+        fs.write(Path::new("/var/empty/file"), b"data").unwrap();
+        assert_eq!(fs.read(Path::new("/var/empty/file")).unwrap(), b"data");
+        fs.write(Path::new("/etc/app.conf"), b"").unwrap();
+        assert_eq!(
+            fs.metadata(Path::new("/etc/app.conf")).unwrap().mode(),
+            0o600
+        );
+        assert!(fs.write(Path::new("/missing/file"), b"").is_err());
+        assert!(fs.write(Path::new("/var/empty"), b"").is_err());
+
+        // The clones are independent
+        let clone = fs.clone();
+        clone.write(Path::new("/var/empty/other"), b"").unwrap();
+        assert!(!fs.exists(Path::new("/var/empty/other")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic() {
+        use crate::test_util::TempDir;
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = TempDir::new();
+        let path = dir.join("file");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        // The temporary names are never opened if they already exist
+        let victim = dir.join("victim");
+        fs::write(&victim, "victim").unwrap();
+        for n in 0..MAX_TEMP_ATTEMPTS / 2 {
+            symlink(&victim, dir.join(format!("file.{}.{n}.tmp", process::id()))).unwrap();
+        }
+
+        StdFileSystem.write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(fs::read(&victim).unwrap(), b"victim");
+        let names = StdFileSystem.read_dir(dir.path()).unwrap();
+        assert_eq!(names.len(), MAX_TEMP_ATTEMPTS / 2 + 2);
+
+        StdFileSystem
+            .write_atomic(&dir.join("new"), b"content")
+            .unwrap();
+        assert_eq!(fs::read(dir.join("new")).unwrap(), b"content");
+        assert!(StdFileSystem
+            .write_atomic(&dir.join("missing/file"), b"")
+            .is_err());
+    }
+}
@@ -0,0 +1,684 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, FileSystem, LocateOption, LocationType, StandardPaths};
+
+/// Options of [`StandardPaths::locate_glob`].
+///
+/// Could be constructed from a [`LocateOption`] for the unlimited depth.
+///
+/// ### Example
+/// ```
+/// use standard_paths::{GlobOptions, LocateOption};
+///
+/// let options = GlobOptions::new()
+///     .option(LocateOption::LocateFile)
+///     .max_depth(2);
+/// assert_eq!(options.get_max_depth(), Some(2));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobOptions {
+    /// The type of entries to search.
+    option: LocateOption,
+    /// The maximum number of components of the matched relative paths.
+    max_depth: Option<usize>,
+}
+
+impl Default for GlobOptions {
+    fn default() -> Self {
+        GlobOptions {
+            option: LocateOption::LocateBoth,
+            max_depth: None,
+        }
+    }
+}
+
+impl From<LocateOption> for GlobOptions {
+    fn from(option: LocateOption) -> Self {
+        GlobOptions::new().option(option)
+    }
+}
+
+impl GlobOptions {
+    /// Constructs new [`GlobOptions`] searching for the entries of any type
+    /// with the unlimited depth.
+    pub fn new() -> GlobOptions {
+        Default::default()
+    }
+
+    /// Sets the type of entries to search.
+    pub fn option(mut self, option: LocateOption) -> GlobOptions {
+        self.option = option;
+        self
+    }
+
+    /// Limits the number of components of the matched paths relative to the base
+    /// directory, e.g. `1` matches only the direct children of the base directories.
+    pub fn max_depth(mut self, depth: usize) -> GlobOptions {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Returns the type of entries to search.
+    pub fn get_option(&self) -> LocateOption {
+        self.option
+    }
+
+    /// Returns the maximum depth if it's limited.
+    pub fn get_max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+}
+
+/// A path found by [`StandardPaths::locate_glob`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobMatch {
+    /// The standard location directory the path was found in.
+    base: PathBuf,
+    /// The full path.
+    path: PathBuf,
+}
+
+impl GlobMatch {
+    /// Returns the standard location directory the path was found in.
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// Returns the full path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path relative to the base directory.
+    pub fn relative(&self) -> &Path {
+        self.path.strip_prefix(&self.base).unwrap_or(&self.path)
+    }
+
+    /// Converts into the full path.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+/// A single element of a path component pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A literal character.
+    Literal(char),
+    /// `?` - any single character.
+    Any,
+    /// `*` - any sequence of characters.
+    Star,
+    /// `[...]` - a character from the set.
+    Class {
+        /// `true` for `[!...]` and `[^...]`.
+        negated: bool,
+        /// Inclusive character ranges.
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// A compiled path component pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**` - any number of directories.
+    Recursive,
+    /// A component without wildcards.
+    Literal(String),
+    /// A component with wildcards.
+    Pattern(Vec<Token>),
+}
+
+/// Compiles the `/`-separated `pattern`.
+fn compile(pattern: &str) -> Result<Vec<Segment>, String> {
+    if pattern.is_empty() {
+        return Err("the pattern is empty".into());
+    }
+    if pattern.starts_with('/') {
+        return Err("the pattern must be relative".into());
+    }
+    let mut segments = Vec::new();
+    for component in pattern.split('/') {
+        match component {
+            "" | "." => continue,
+            ".." => return Err("the pattern must not leave the base directory".into()),
+            "**" => {
+                if segments.last() != Some(&Segment::Recursive) {
+                    segments.push(Segment::Recursive);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let tokens = compile_segment(component)?;
+        let literal = tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(c) => Some(*c),
+                _ => None,
+            })
+            .collect::<Option<String>>();
+        segments.push(match literal {
+            Some(literal) => Segment::Literal(literal),
+            _ => Segment::Pattern(tokens),
+        });
+    }
+    if segments.is_empty() {
+        return Err("the pattern is empty".into());
+    }
+    Ok(segments)
+}
+
+/// Compiles a single path `component` pattern.
+fn compile_segment(component: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '?' => Token::Any,
+            '*' => {
+                if tokens.last() == Some(&Token::Star) {
+                    continue;
+                }
+                Token::Star
+            }
+            '\\' => match chars.next() {
+                Some(c) => Token::Literal(c),
+                _ => return Err(format!("trailing escape in '{component}'")),
+            },
+            '[' => {
+                let mut negated = false;
+                let mut ranges = Vec::new();
+                let mut first = true;
+                loop {
+                    let c = match chars.next() {
+                        Some(c) => c,
+                        _ => return Err(format!("unclosed character class in '{component}'")),
+                    };
+                    match c {
+                        '!' | '^' if first && !negated => {
+                            negated = true;
+                            continue;
+                        }
+                        ']' if !first => break,
+                        _ => {}
+                    }
+                    first = false;
+                    let start = match c {
+                        '\\' => chars
+                            .next()
+                            .ok_or_else(|| format!("trailing escape in '{component}'"))?,
+                        c => c,
+                    };
+                    // A range unless the dash is the last character of the class
+                    let mut lookahead = chars.clone();
+                    match (lookahead.next(), lookahead.next()) {
+                        (Some('-'), Some(end)) if end != ']' => {
+                            if end < start {
+                                return Err(format!(
+                                    "invalid range '{start}-{end}' in '{component}'"
+                                ));
+                            }
+                            ranges.push((start, end));
+                            chars = lookahead;
+                        }
+                        _ => ranges.push((start, start)),
+                    }
+                }
+                Token::Class { negated, ranges }
+            }
+            c => Token::Literal(c),
+        });
+    }
+    Ok(tokens)
+}
+
+/// Checks the `name` against the `tokens`.
+///
+/// The wildcards do not match the leading dot of the hidden entries.
+/// The invalid UTF-8 sequences of the name are matched as the replacement character.
+fn matches(tokens: &[Token], name: &str) -> bool {
+    if name.starts_with('.') && tokens.first() != Some(&Token::Literal('.')) {
+        return false;
+    }
+    let name = name.chars().collect::<Vec<_>>();
+    matches_chars(tokens, &name)
+}
+
+fn matches_chars(tokens: &[Token], name: &[char]) -> bool {
+    match tokens.split_first() {
+        None => name.is_empty(),
+        Some((Token::Star, rest)) => (0..=name.len()).any(|i| matches_chars(rest, &name[i..])),
+        Some((token, rest)) => match name.split_first() {
+            Some((c, name)) => {
+                let hit = match token {
+                    Token::Literal(literal) => literal == c,
+                    Token::Any => true,
+                    Token::Class { negated, ranges } => {
+                        ranges.iter().any(|(start, end)| (start..=end).contains(&c)) != *negated
+                    }
+                    Token::Star => unreachable!(),
+                };
+                hit && matches_chars(rest, name)
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Walks the directory tree of a single base directory.
+struct Walker<'a> {
+    /// The filesystem the entries are listed on.
+    fs: &'a dyn FileSystem,
+    /// The search options.
+    options: GlobOptions,
+    /// The canonical paths of the directories being walked by `**`.
+    ancestors: Vec<PathBuf>,
+    /// The found paths.
+    found: Vec<PathBuf>,
+}
+
+impl Walker<'_> {
+    /// Matches the `segments` against the entries of `dir` located `depth`
+    /// components below the base directory.
+    fn walk(&mut self, dir: &Path, depth: usize, segments: &[Segment]) {
+        let (segment, rest) = match segments.split_first() {
+            Some(split) => split,
+            _ => return,
+        };
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+        match segment {
+            Segment::Recursive => {
+                // A symbolic link to a directory being walked would lead to a cycle
+                let canonical = match self.fs.canonicalize(dir) {
+                    Ok(path) if !self.ancestors.contains(&path) => path,
+                    _ => return,
+                };
+                if !rest.is_empty() {
+                    self.walk(dir, depth, rest);
+                }
+                self.ancestors.push(canonical);
+                for name in self.list(dir) {
+                    if name.to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                    let path = dir.join(&name);
+                    // A trailing `**` matches every entry inside the directory
+                    if rest.is_empty() {
+                        self.visit(path.clone(), depth + 1, rest);
+                    }
+                    if self.is_dir(&path) {
+                        self.walk(&path, depth + 1, segments);
+                    }
+                }
+                self.ancestors.pop();
+            }
+            Segment::Literal(name) => self.visit(dir.join(name), depth + 1, rest),
+            Segment::Pattern(tokens) => {
+                for name in self.list(dir) {
+                    if matches(tokens, &name.to_string_lossy()) {
+                        self.visit(dir.join(name), depth + 1, rest);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles the `path` matched by a segment.
+    fn visit(&mut self, path: PathBuf, depth: usize, rest: &[Segment]) {
+        if rest.is_empty() {
+            if self.options.option.check(self.fs, &path).unwrap_or(false) {
+                self.found.push(path);
+            }
+        } else if self.is_dir(&path) {
+            self.walk(&path, depth, rest);
+        }
+    }
+
+    /// Returns `true` if the walk could descend into `path`.
+    ///
+    /// The symbolic links are not traversed with [`LocateOption::NoFollow`]
+    /// or [`LocateOption::LocateSymlink`].
+    fn is_dir(&self, path: &Path) -> bool {
+        let no_follow = LocateOption::NoFollow | LocateOption::LocateSymlink;
+        if self.options.option.intersects(no_follow) {
+            self.fs.symlink_metadata(path).is_ok_and(|md| md.is_dir())
+        } else {
+            self.fs.is_dir(path)
+        }
+    }
+
+    /// Returns the names of the entries of `dir`.
+    ///
+    /// The directories which cannot be listed are treated as empty.
+    fn list(&self, dir: &Path) -> Vec<OsString> {
+        self.fs.read_dir(dir).unwrap_or_default()
+    }
+}
+
+impl StandardPaths {
+    /// Search for all files or directories matching the glob `pattern`
+    /// in the standard locations.
+    ///
+    /// The pattern is a relative path with `/` as the separator on all platforms
+    /// and supports the following wildcards:
+    /// * `?` - any single character;
+    /// * `*` - any sequence of characters within a path component;
+    /// * `**` - any number of nested directories including none,
+    ///   or every nested entry at the end of the pattern;
+    /// * `[abc]`, `[a-z]`, `[!a-z]` - a character from the set or not from it.
+    ///
+    /// A wildcard could be escaped with `\`. The wildcards do not match
+    /// the names starting with a dot. The names which are not valid UTF-8 are matched
+    /// with the invalid sequences replaced by `U+FFFD`, so only the wildcards match them.
+    ///
+    /// The symbolic links to directories are traversed by all the pattern components
+    /// unless the options contain [`LocateOption::NoFollow`] or [`LocateOption::LocateSymlink`].
+    /// `**` does not descend into a link to a directory it's already walking.
+    ///
+    /// Returns the matches in the priority order of
+    /// [`StandardPaths::standard_locations`] and sorted by path within each base directory.
+    ///
+    /// Returns [`Error::InvalidGlob`] if the pattern is malformed or
+    /// [`Error`] if accessing the `location` failed.
+    ///
+    /// # Arguments
+    /// * `location` - the location type where to search.
+    /// * `pattern` - the glob pattern relative to the location directories.
+    /// * `options` - the type of entries to search and the maximum depth.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{GlobOptions, LocateOption, LocationType, MapEnvironment, MemoryFileSystem, StandardPaths};
+    /// use std::path::Path;
+    ///
+    /// let fs = MemoryFileSystem::new()
+    ///     .with_file("/home/user/.local/share/app/themes/dark.toml")
+    ///     .with_file("/home/user/.local/share/app/themes/light.toml")
+    ///     .with_file("/home/user/.local/share/app/plugins/a/init.lua")
+    ///     .with_file("/usr/share/app/themes/default.toml");
+    /// let sp = StandardPaths::builder()
+    ///     .app_name("app")
+    ///     .environment(
+    ///         MapEnvironment::new()
+    ///             .with_home("/home/user")
+    ///             .with_var("XDG_DATA_DIRS", "/usr/share"),
+    ///     )
+    ///     .file_system(fs)
+    ///     .build();
+    /// # #[cfg(target_os = "linux")]
+    /// # {
+    /// let themes = sp
+    ///     .locate_glob(LocationType::AppDataLocation, "themes/*.toml", LocateOption::LocateFile)
+    ///     .unwrap();
+    /// let names = themes.iter().map(|m| m.path()).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     names,
+    ///     [
+    ///         Path::new("/home/user/.local/share/app/themes/dark.toml"),
+    ///         Path::new("/home/user/.local/share/app/themes/light.toml"),
+    ///         Path::new("/usr/share/app/themes/default.toml"),
+    ///     ]
+    /// );
+    /// assert_eq!(themes[2].base(), Path::new("/usr/share/app"));
+    ///
+    /// let scripts = sp
+    ///     .locate_glob(LocationType::AppDataLocation, "**/*.lua", GlobOptions::new())
+    ///     .unwrap();
+    /// assert_eq!(scripts[0].relative(), Path::new("plugins/a/init.lua"));
+    ///
+    /// let options = GlobOptions::new().max_depth(2);
+    /// let scripts = sp
+    ///     .locate_glob(LocationType::AppDataLocation, "**/*.lua", options)
+    ///     .unwrap();
+    /// assert!(scripts.is_empty());
+    /// # }
+    /// ```
+    pub fn locate_glob<S, O>(
+        &self,
+        location: LocationType,
+        pattern: S,
+        options: O,
+    ) -> Result<Vec<GlobMatch>, Error>
+    where
+        S: AsRef<str>,
+        O: Into<GlobOptions>,
+    {
+        let pattern = pattern.as_ref();
+        let segments = compile(pattern).map_err(|reason| Error::InvalidGlob {
+            pattern: pattern.to_owned(),
+            reason,
+        })?;
+        let mut walker = Walker {
+            fs: self.fs.as_ref(),
+            options: options.into(),
+            ancestors: Vec::new(),
+            found: Vec::new(),
+        };
+        let mut res = Vec::new();
+        for base in self.standard_locations(location)? {
+            walker.walk(&base, 0, &segments);
+            let mut found = std::mem::take(&mut walker.found);
+            found.sort();
+            found.dedup();
+            res.extend(found.into_iter().map(|path| GlobMatch {
+                base: base.clone(),
+                path,
+            }));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapEnvironment, MemoryFileSystem};
+
+    fn matches_pattern(pattern: &str, name: &str) -> bool {
+        matches(&compile_segment(pattern).unwrap(), name)
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let err = |pattern| compile(pattern).unwrap_err();
+        assert_eq!(err(""), "the pattern is empty");
+        assert_eq!(err("./"), "the pattern is empty");
+        assert_eq!(err("/etc/*"), "the pattern must be relative");
+        assert_eq!(
+            err("a/../b"),
+            "the pattern must not leave the base directory"
+        );
+        assert_eq!(err(r"a\"), r"trailing escape in 'a\'");
+        assert_eq!(err("[ab"), "unclosed character class in '[ab'");
+        assert_eq!(err("[]"), "unclosed character class in '[]'");
+        assert_eq!(err("[z-a]"), "invalid range 'z-a' in '[z-a]'");
+    }
+
+    #[test]
+    fn segments() {
+        assert_eq!(
+            compile("./a//**/**/b*").unwrap(),
+            [
+                Segment::Literal("a".into()),
+                Segment::Recursive,
+                Segment::Pattern(vec![Token::Literal('b'), Token::Star]),
+            ]
+        );
+        assert_eq!(
+            compile(r"\*\[x]").unwrap(),
+            [Segment::Literal("*[x]".into())]
+        );
+        assert_eq!(
+            compile_segment("a**?").unwrap(),
+            [Token::Literal('a'), Token::Star, Token::Any]
+        );
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches_pattern("*.toml", "a.toml"));
+        assert!(!matches_pattern("*.toml", ".toml.toml"));
+        assert!(matches_pattern(".*", ".hidden"));
+        assert!(!matches_pattern("?hidden", ".hidden"));
+        assert!(!matches_pattern("[.]hidden", ".hidden"));
+        assert!(matches_pattern("a*b*c", "abbbc"));
+        assert!(!matches_pattern("a*b*c", "abbb"));
+        assert!(matches_pattern("??", "äö"));
+        assert!(!matches_pattern("??", "a"));
+        assert!(matches_pattern(r"\?", "?"));
+        assert!(!matches_pattern(r"\?", "a"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches_pattern("[a-c]", "b"));
+        assert!(!matches_pattern("[a-c]", "d"));
+        assert!(matches_pattern("[!a-c]", "d"));
+        assert!(matches_pattern("[^a-c]", "d"));
+        assert!(!matches_pattern("[!a-c]", "a"));
+        // A leading bracket and a trailing dash are literal
+        assert!(matches_pattern("[]]", "]"));
+        assert!(matches_pattern("[!]]", "a"));
+        assert!(matches_pattern("[a-]", "-"));
+        assert!(!matches_pattern("[a-]", "b"));
+        assert!(matches_pattern(r"[\]x]", "]"));
+        assert!(matches_pattern("[!!]", "a"));
+        assert!(!matches_pattern("[!!]", "!"));
+    }
+
+    fn paths(fs: MemoryFileSystem) -> StandardPaths {
+        StandardPaths::builder()
+            .app_name("app")
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("XDG_CONFIG_HOME", "/config")
+                    .with_var("XDG_CONFIG_DIRS", "/etc/xdg"),
+            )
+            .file_system(fs)
+            .build()
+    }
+
+    fn relative<O>(sp: &StandardPaths, pattern: &str, options: O) -> Vec<String>
+    where
+        O: Into<GlobOptions>,
+    {
+        sp.locate_glob(LocationType::AppConfigLocation, pattern, options)
+            .unwrap()
+            .iter()
+            .map(|m| m.relative().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn walking() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/config/app/a.conf")
+            .with_file("/config/app/.hidden/b.conf")
+            .with_file("/config/app/sub/c.conf")
+            .with_file("/config/app/sub/deep/d.conf")
+            .with_symlink("/config/app/link", "/config/app/sub")
+            .with_file("/etc/xdg/app/a.conf");
+        let sp = paths(fs);
+
+        assert_eq!(
+            relative(&sp, "**/*.conf", LocateOption::LocateFile),
+            [
+                "a.conf",
+                "link/c.conf",
+                "link/deep/d.conf",
+                "sub/c.conf",
+                "sub/deep/d.conf",
+                "a.conf"
+            ]
+        );
+        assert_eq!(
+            relative(&sp, "*/*.conf", GlobOptions::new()),
+            ["link/c.conf", "sub/c.conf"]
+        );
+        assert_eq!(
+            relative(&sp, ".hidden/*", GlobOptions::new()),
+            [".hidden/b.conf"]
+        );
+        assert_eq!(
+            relative(&sp, "sub/**", GlobOptions::new()),
+            ["sub/c.conf", "sub/deep", "sub/deep/d.conf"]
+        );
+        assert_eq!(
+            relative(&sp, "**", LocateOption::LocateDirectory),
+            ["link", "link/deep", "sub", "sub/deep"]
+        );
+        assert_eq!(
+            relative(&sp, "**", GlobOptions::new().max_depth(1)),
+            ["a.conf", "link", "sub", "a.conf"]
+        );
+        assert_eq!(
+            relative(&sp, "**/*.conf", GlobOptions::new().max_depth(2)),
+            ["a.conf", "link/c.conf", "sub/c.conf", "a.conf"]
+        );
+        assert!(relative(&sp, "missing/**", GlobOptions::new()).is_empty());
+
+        match sp.locate_glob(LocationType::AppConfigLocation, "../*", GlobOptions::new()) {
+            Err(Error::InvalidGlob { pattern, .. }) => assert_eq!(pattern, "../*"),
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn symlinks() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/config/app/sub/c.conf")
+            .with_symlink("/config/app/link", "/config/app/sub")
+            .with_symlink("/config/app/sub/up", "/config/app");
+        let sp = paths(fs);
+
+        // The cycles are entered once by `**`
+        assert_eq!(
+            relative(&sp, "**/*.conf", LocateOption::LocateFile),
+            ["link/c.conf", "sub/c.conf"]
+        );
+        assert_eq!(
+            relative(&sp, "sub/up/*/c.conf", GlobOptions::new()),
+            ["sub/up/link/c.conf", "sub/up/sub/c.conf"]
+        );
+        // No segment traverses the links with `NoFollow`
+        let no_follow = LocateOption::LocateFile | LocateOption::NoFollow;
+        assert_eq!(relative(&sp, "**/*.conf", no_follow), ["sub/c.conf"]);
+        assert_eq!(relative(&sp, "*/c.conf", no_follow), ["sub/c.conf"]);
+        assert!(relative(&sp, "link/c.conf", no_follow).is_empty());
+        assert_eq!(
+            relative(&sp, "**", LocateOption::LocateSymlink),
+            ["link", "sub/up"]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let name = OsStr::from_bytes(b"caf\xe9.conf");
+        let fs = MemoryFileSystem::new()
+            .with_file(Path::new("/config/app").join(name))
+            .with_file("/config/app/a.conf");
+        let sp = paths(fs);
+
+        let found = sp
+            .locate_glob(
+                LocationType::AppConfigLocation,
+                "*.conf",
+                GlobOptions::new(),
+            )
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].relative(), Path::new(name));
+        assert_eq!(relative(&sp, "caf?.conf", GlobOptions::new()).len(), 1);
+        assert!(relative(&sp, "caf[e].conf", GlobOptions::new()).is_empty());
+    }
+}
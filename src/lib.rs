@@ -17,6 +17,26 @@
 #![warn(missing_docs)]
 #![warn(clippy::doc_markdown)]
 
+mod builder;
+mod display_name;
+mod ensure;
+mod environment;
+mod error;
+mod filesystem;
+mod glob;
+mod locate;
+mod location_type;
+mod overlay;
+mod overrides;
+mod probe;
+mod query;
+mod snapshot;
+mod test_mode;
+#[cfg(test)]
+mod test_util;
+mod user_dir_names;
+mod user_dirs;
+
 #[cfg(target_os = "linux")]
 mod linux;
 
@@ -29,9 +49,28 @@ mod windows;
 #[cfg(windows)]
 use windows::*;
 
+pub use builder::{NamingPolicy, StandardPathsBuilder, Strictness};
+pub use ensure::EnsuredPath;
+pub use environment::{Environment, MapEnvironment, ProcessEnvironment};
+pub use error::{Error, RuntimeDirIssue};
+pub use filesystem::{FileKind, FileSystem, MemoryFileSystem, Metadata, StdFileSystem};
+pub use glob::{GlobMatch, GlobOptions};
+pub use locate::{LocateIter, LocateOption, ParseLocateOptionError};
+pub use location_type::ParseLocationTypeError;
+pub use overlay::{Overlay, OverlayEntry, OverlayReadDir};
+pub use probe::{Candidate, ProbedLocation, Rejection};
+pub use query::{LocateQuery, QueryHit, QueryOrder};
+pub use snapshot::{LocationSnapshot, Snapshot};
+pub use user_dirs::{UserDirDefaults, UserDirEntry, UserDirs, UserDirsConfig};
+
 use std::env;
-use std::io::{Error, ErrorKind};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(target_os = "linux")]
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Constructs a new [`StandardPaths`] with the application name
 /// derived from the `CARGO_PKG_NAME` variable.
@@ -58,13 +97,18 @@ macro_rules! default_paths {
 ///
 /// Some of the values are used to acquire user-specific paths,
 /// some are application-specific and some are system-wide.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The enum is non-exhaustive, so the new location types could be added
+/// in the minor releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum LocationType {
     /// The user's home directory.
     ///
     /// * On Linux systems it's equal to the `$HOME` environment variable.
     /// * On the last Windows operating systems it's equal to the `%HomePath%`
-    /// environment variable.
+    ///   environment variable.
     HomeLocation,
     /// The user's desktop directory.
     DesktopLocation,
@@ -135,21 +179,29 @@ pub enum LocationType {
     ///
     /// This is an application-specific value.
     AppConfigLocation,
-}
-
-/// Enumerates the locate option type.
-///
-/// Is used to call
-/// [`StandardPaths::locate`] and
-/// [`StandardPaths::locate_all`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LocateOption {
-    /// Locate both files and directories (traversing symbolic links).
-    LocateBoth,
-    /// Locate only files.
-    LocateFile,
-    /// Locate only directories.
-    LocateDirectory,
+    /// The directory for the user-specific state data shared across applications,
+    /// like histories or logs, which should persist but are not portable enough
+    /// to be stored in [`LocationType::GenericDataLocation`].
+    ///
+    /// This is a generic value.
+    GenericStateLocation,
+    /// The user-specific state data directory.
+    ///
+    /// This is an application-specific value.
+    ///
+    /// * On Linux systems it's located in `$XDG_STATE_HOME` (`~/.local/state` by default).
+    /// * On Windows it's the `State` subdirectory of [`LocationType::AppLocalDataLocation`].
+    AppStateLocation,
+    /// The user's templates directory.
+    TemplatesLocation,
+    /// The user's directory for the files shared with the other users.
+    PublicShareLocation,
+    /// The user-specific executables directory.
+    ///
+    /// * On Linux systems it's equal to the `$XDG_BIN_HOME` environment variable
+    ///   (`~/.local/bin` by default).
+    /// * On Windows it's `%LocalAppData%\Programs`.
+    UserExecutablesLocation,
 }
 
 /// Stores application and organization names and provides all the crate methods.
@@ -158,6 +210,27 @@ pub struct StandardPaths {
     app_name: String,
     /// organization name.
     org_name: String,
+    /// Organization domain.
+    domain: String,
+    /// Application suffix policy.
+    naming: NamingPolicy,
+    /// Environment variables validation mode.
+    strictness: Strictness,
+    /// Additional search directories.
+    search_dirs: Vec<(LocationType, PathBuf)>,
+    /// Per-location overrides.
+    overrides: Vec<(LocationType, PathBuf)>,
+    /// Override variables prefix.
+    env_prefix: Option<String>,
+    /// Test mode sandbox root.
+    test_root: Option<test_mode::TestRoot>,
+    /// Environment the locations are resolved in.
+    env: Arc<dyn Environment>,
+    /// Filesystem the lookups are performed on.
+    fs: Arc<dyn FileSystem>,
+    /// Test mode sandbox root prepared by the instance.
+    #[cfg(target_os = "linux")]
+    prepared_test_root: Mutex<Option<PathBuf>>,
 }
 
 impl StandardPaths {
@@ -166,10 +239,7 @@ impl StandardPaths {
     where
         S: Into<String>,
     {
-        StandardPaths {
-            app_name: app.into(),
-            org_name: org.into(),
-        }
+        StandardPaths::builder().app_name(app).org_name(org).build()
     }
 
     /// Constructs a new [`StandardPaths`] with the provided `app` name and with an empty organization.
@@ -177,21 +247,130 @@ impl StandardPaths {
     where
         S: Into<String>,
     {
-        StandardPaths {
-            app_name: app.into(),
-            org_name: Default::default(),
+        StandardPaths::builder().app_name(app).build()
+    }
+
+    /// Constructs a new [`StandardPaths`] with the provided `app` and `org` names
+    /// which resolves the locations in the provided `env` instead of
+    /// the environment of the current process.
+    pub fn with_environment<S, E>(app: S, org: S, env: E) -> StandardPaths
+    where
+        S: Into<String>,
+        E: Environment + 'static,
+    {
+        StandardPaths::builder()
+            .app_name(app)
+            .org_name(org)
+            .environment(env)
+            .build()
+    }
+
+    /// Returns a new [`StandardPathsBuilder`] to configure a [`StandardPaths`].
+    pub fn builder() -> StandardPathsBuilder {
+        StandardPathsBuilder::new()
+    }
+
+    /// Returns the application name.
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    /// Returns the organization name.
+    pub fn org_name(&self) -> &str {
+        &self.org_name
+    }
+
+    /// Returns the organization domain.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns the way the application-specific suffix is built.
+    pub fn naming_policy(&self) -> NamingPolicy {
+        self.naming
+    }
+
+    /// Returns the way the XDG base directory environment variables are validated.
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// Returns the errors describing every environment variable value which is ignored
+    /// with the current [`Strictness`], e.g. an empty `XDG_CACHE_HOME`
+    /// or a relative entry of `XDG_DATA_DIRS` in the strict mode.
+    ///
+    /// Every error is an [`Error::InvalidEnvVar`]. The list is always empty on Windows.
+    pub fn validate_environment(&self) -> Vec<Error> {
+        self.validate_environment_impl()
+    }
+
+    /// Returns the environment the locations are resolved in.
+    pub fn environment(&self) -> &dyn Environment {
+        self.env.as_ref()
+    }
+
+    /// Sets the filesystem the [`StandardPaths::locate`] and
+    /// [`StandardPaths::locate_all`] lookups are performed on.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::*;
+    /// use std::path::PathBuf;
+    ///
+    /// let env = MapEnvironment::new().with_home("/home/user");
+    /// let fs = MemoryFileSystem::new().with_file("/home/user/.config/org/app/app.conf");
+    /// let mut sp = StandardPaths::with_environment("app", "org", env);
+    /// sp.set_file_system(fs);
+    /// assert_eq!(
+    ///     sp.locate(LocationType::AppConfigLocation, "app.conf", LocateOption::LocateFile)
+    ///         .unwrap(),
+    ///     Some(PathBuf::from("/home/user/.config/org/app/app.conf"))
+    /// );
+    /// ```
+    pub fn set_file_system<F>(&mut self, fs: F)
+    where
+        F: FileSystem + 'static,
+    {
+        self.fs = Arc::new(fs);
+        // The sandbox is prepared again on the new filesystem
+        #[cfg(target_os = "linux")]
+        {
+            *self
+                .prepared_test_root
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner) = None;
         }
     }
 
+    /// Returns the filesystem the lookups are performed on.
+    pub fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+
     /// Append application suffix to the `path`.
     ///
     /// For example `~/.config` -> `~/.config/org/app`.
+    /// The suffix depends on the [`NamingPolicy`].
     ///
     /// # Arguments
     /// * `path` - a mutable [`PathBuf`] to which the app suffix should be appended.
     fn append_organization_and_app(&self, path: &mut PathBuf) {
-        if !self.org_name.is_empty() {
-            path.push(&self.org_name);
+        match self.naming {
+            NamingPolicy::ReverseDomain if !self.domain.is_empty() => {
+                let mut id = self.domain.rsplit('.').collect::<Vec<_>>().join(".");
+                if !self.app_name.is_empty() {
+                    id.push('.');
+                    id.push_str(&self.app_name);
+                }
+                path.push(id);
+                return;
+            }
+            NamingPolicy::Application => {}
+            _ => {
+                if !self.org_name.is_empty() {
+                    path.push(&self.org_name);
+                }
+            }
         }
         if !self.app_name.is_empty() {
             path.push(&self.app_name);
@@ -202,19 +381,26 @@ impl StandardPaths {
     ///
     /// Note: the returned path can be a directory that does not exist.
     ///
+    /// If the location is overridden (see [`StandardPaths::location_override`])
+    /// the override is returned.
+    ///
     /// Returns [`Error`] if the location cannot be determined.
     ///
     /// # Arguments
     /// * `location` - location type.
     pub fn writable_location(&self, location: LocationType) -> Result<PathBuf, Error> {
-        self.writable_location_impl(location)
+        match self.location_override(location) {
+            Some(path) => Ok(path),
+            _ => self.writable_location_impl(location),
+        }
     }
 
     /// Returns all the directories of type `location`.
     ///
     /// The vector of locations is sorted by priority, starting with
     /// [self.writable location](struct.StandardPaths.html#method.writable_location)
-    /// if it can be determined.
+    /// if it can be determined. The extra directories added with
+    /// [`StandardPathsBuilder::search_dir`] are appended to the end.
     ///
     /// Returns [`Error`] if the locations cannot be determined or
     /// an empty vector if no locations for the provided type are defined.
@@ -222,7 +408,19 @@ impl StandardPaths {
     /// # Arguments
     /// * `location` - location type.
     pub fn standard_locations(&self, location: LocationType) -> Result<Vec<PathBuf>, Error> {
-        self.standard_locations_impl(location)
+        let mut res = self.standard_locations_impl(location)?;
+        if let Some(path) = self.location_override(location) {
+            match res.first_mut() {
+                Some(first) => *first = path,
+                _ => res.push(path),
+            }
+        }
+        for (loc, path) in &self.search_dirs {
+            if *loc == location && !res.contains(path) {
+                res.push(path.clone());
+            }
+        }
+        Ok(res)
     }
 
     /// Returns the absolute file path to the executable with `name` in the system path.
@@ -241,20 +439,46 @@ impl StandardPaths {
     ///
     /// # Arguments
     /// * `name` - the name of the searched executable or an absolute path
-    /// which should be checked to be executable.
+    ///   which should be checked to be executable.
     pub fn find_executable<S>(name: S) -> Option<Vec<PathBuf>>
     where
         S: Into<String>,
     {
-        // Read system paths
-        let path_var = match env::var("PATH") {
-            Ok(var) => var,
-            _ => return None,
-        };
-        let paths: Vec<PathBuf> = env::split_paths(&path_var).collect();
+        let paths = system_paths(&ProcessEnvironment)?;
         StandardPaths::find_executable_in_paths(name, paths)
     }
 
+    /// Returns `true` if the [`LocationType::UserExecutablesLocation`] directory
+    /// is listed in the system path of the instance environment.
+    ///
+    /// The directories are compared both literally and after resolving
+    /// the symbolic links.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{MapEnvironment, StandardPaths};
+    ///
+    /// # #[cfg(target_os = "linux")]
+    /// # {
+    /// let env = MapEnvironment::new()
+    ///     .with_home("/home/user")
+    ///     .with_var("PATH", "/home/user/.local/bin:/usr/bin");
+    /// let sp = StandardPaths::with_environment("app", "org", env);
+    /// assert!(sp.is_user_executables_on_path().unwrap());
+    /// # }
+    /// ```
+    pub fn is_user_executables_on_path(&self) -> Result<bool, Error> {
+        let dir = self.writable_location(LocationType::UserExecutablesLocation)?;
+        let paths = match system_paths(self.environment()) {
+            Some(paths) => paths,
+            _ => return Ok(false),
+        };
+        let canonical = self.fs.canonicalize(&dir).ok();
+        Ok(paths.iter().any(|path| {
+            *path == dir || (canonical.is_some() && self.fs.canonicalize(path).ok() == canonical)
+        }))
+    }
+
     /// Returns the absolute file path to the executable with `name` in the provided `paths`.
     ///
     /// Note: on Windows the executable extensions from the `PATHEXT` environment variable
@@ -264,14 +488,33 @@ impl StandardPaths {
     ///
     /// # Arguments
     /// * `name` - the name of the searched executable or an absolute path
-    /// which should be checked to be executable.
+    ///   which should be checked to be executable.
     /// * `paths` - the directories where to search for the executable.
     pub fn find_executable_in_paths<S, P>(name: S, paths: P) -> Option<Vec<PathBuf>>
     where
         S: Into<String>,
         P: AsRef<Vec<PathBuf>>,
     {
-        find_executable_in_paths_impl(name, &paths)
+        StandardPaths::find_executable_in_paths_with(&StdFileSystem, name, paths)
+    }
+
+    /// Returns the absolute file path to the executable with `name` in the provided `paths`
+    /// searching on the provided filesystem `fs`.
+    ///
+    /// See [`StandardPaths::find_executable_in_paths`] for details.
+    ///
+    /// # Arguments
+    /// * `fs` - the filesystem where to search.
+    /// * `name` - the name of the searched executable or an absolute path
+    ///   which should be checked to be executable.
+    /// * `paths` - the directories where to search for the executable.
+    pub fn find_executable_in_paths_with<F, S, P>(fs: &F, name: S, paths: P) -> Option<Vec<PathBuf>>
+    where
+        F: FileSystem + ?Sized,
+        S: Into<String>,
+        P: AsRef<Vec<PathBuf>>,
+    {
+        find_executable_in_paths_impl(fs, name, &paths)
     }
 
     /// Search for a file or directory called 'name' in the standard locations.
@@ -297,22 +540,8 @@ impl StandardPaths {
         let paths = self.standard_locations(location)?;
         for mut path in paths {
             path.push(&name);
-            match option {
-                LocateOption::LocateBoth => {
-                    if path.exists() {
-                        return Ok(Some(path));
-                    }
-                }
-                LocateOption::LocateFile => {
-                    if path.is_file() {
-                        return Ok(Some(path));
-                    }
-                }
-                LocateOption::LocateDirectory => {
-                    if path.is_dir() {
-                        return Ok(Some(path));
-                    }
-                }
+            if option.check(self.fs.as_ref(), &path).unwrap_or(false) {
+                return Ok(Some(path));
             }
         }
         Ok(None)
@@ -325,6 +554,8 @@ impl StandardPaths {
     /// Returns [`Error`] if accessing the `location` failed or
     /// [`None`] if no such files or directories can be found.
     ///
+    /// See [`StandardPaths::locate_iter`] for the lazy version.
+    ///
     /// # Arguments
     /// * `location` - the location type where to search.
     /// * `name` - the name of the files or directories to search.
@@ -342,22 +573,8 @@ impl StandardPaths {
         let mut res = Vec::new();
         for mut path in paths {
             path.push(&name);
-            match option {
-                LocateOption::LocateBoth => {
-                    if path.exists() {
-                        res.push(path);
-                    }
-                }
-                LocateOption::LocateFile => {
-                    if path.is_file() {
-                        res.push(path);
-                    }
-                }
-                LocateOption::LocateDirectory => {
-                    if path.is_dir() {
-                        res.push(path);
-                    }
-                }
+            if option.check(self.fs.as_ref(), &path).unwrap_or(false) {
+                res.push(path);
             }
         }
         if res.is_empty() {
@@ -367,8 +584,72 @@ impl StandardPaths {
         }
     }
 
-    #[inline]
-    fn home_dir_err() -> Error {
-        Error::new(ErrorKind::Other, "Error getting HOME directory")
+    /// Search for a file or directory called 'name' in the standard locations
+    /// accepted by the `predicate`.
+    ///
+    /// The `predicate` is called with the full path and the metadata of every
+    /// existing entry in the priority order. The metadata is queried traversing
+    /// symbolic links.
+    ///
+    /// Returns a full path to the first accepted file or directory.
+    ///
+    /// Returns [`Error`] if accessing the `location` failed or
+    /// [`None`] if no such file or directory can be found.
+    ///
+    /// # Arguments
+    /// * `location` - the location type where to search.
+    /// * `name` - the name of the file or directory to search.
+    /// * `predicate` - the function checking the found entries.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocationType, MapEnvironment, MemoryFileSystem, StandardPaths};
+    /// use std::path::PathBuf;
+    ///
+    /// let fs = MemoryFileSystem::new()
+    ///     .with_file("/home/user/.config/app.conf")
+    ///     .with_mode("/home/user/.config/app.conf", 0o600)
+    ///     .with_file("/etc/xdg/app.conf");
+    /// let sp = StandardPaths::builder()
+    ///     .environment(MapEnvironment::new().with_home("/home/user"))
+    ///     .file_system(fs)
+    ///     .build();
+    /// # #[cfg(target_os = "linux")]
+    /// assert_eq!(
+    ///     sp.locate_with(LocationType::ConfigLocation, "app.conf", |_, md| {
+    ///         md.is_file() && md.mode() & 0o044 != 0
+    ///     })
+    ///     .unwrap(),
+    ///     Some(PathBuf::from("/etc/xdg/app.conf"))
+    /// );
+    /// ```
+    pub fn locate_with<P, F>(
+        &self,
+        location: LocationType,
+        name: P,
+        predicate: F,
+    ) -> Result<Option<PathBuf>, Error>
+    where
+        P: AsRef<Path>,
+        F: Fn(&Path, &Metadata) -> bool,
+    {
+        let paths = self.standard_locations(location)?;
+        for mut path in paths {
+            path.push(&name);
+            if let Ok(md) = self.fs.metadata(&path) {
+                if predicate(&path, &md) {
+                    return Ok(Some(path));
+                }
+            }
+        }
+        Ok(None)
     }
 }
+
+/// Splits the system path of the `env` into the separate directories.
+///
+/// Returns [`None`] if the `PATH` environment variable is not set.
+fn system_paths(env: &dyn Environment) -> Option<Vec<PathBuf>> {
+    let path_var = env.var_os("PATH")?;
+    Some(env::split_paths(&path_var).collect())
+}
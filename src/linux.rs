@@ -1,54 +1,180 @@
 use std::{
-    collections::HashMap,
-    env, fs,
-    io::{BufRead, BufReader, Error, ErrorKind},
-    os::{linux::fs::MetadataExt, unix::fs::PermissionsExt},
-    path::PathBuf,
+    ffi::{CString, OsStr},
+    fs, io,
+    os::{
+        linux::fs::MetadataExt,
+        unix::{
+            ffi::OsStrExt,
+            fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt},
+        },
+    },
+    path::{Path, PathBuf},
 };
 
-use crate::{LocationType, StandardPaths};
+use nix::libc;
+
+use crate::{
+    display_name::messages_locale, user_dir_names::translate_user_dir_path, user_dirs::USER_DIRS,
+    Environment, Error, FileSystem, LocationType, RuntimeDirIssue, StandardPaths, Strictness,
+    UserDirDefaults, UserDirs, UserDirsConfig,
+};
 
 macro_rules! get_var_or_home {
-    ($var_name:expr, $($sub_dirs:expr),*) => {
-        match env::var($var_name) {
-            Ok(path) => PathBuf::from(path),
-            _ => match home::home_dir() {
+    ($env:expr, $strictness:expr, $location:expr, $var_name:expr, $($sub_dirs:expr),*) => {
+        match xdg_base_dir($env, $strictness, $var_name) {
+            Some(path) => path,
+            _ => match $env.home_dir() {
                 Some(mut path) => {
                     $(
                         path.push($sub_dirs);
                     )*
                     path
                 },
-                _ => return Err(StandardPaths::home_dir_err())
+                _ => return Err(Error::HomeNotFound { location: $location })
             }
         }
     }
 }
 
-fn xdg_config_dirs() -> Vec<PathBuf> {
-    // http://standards.freedesktop.org/basedir-spec/latest/
-    match env::var("XDG_CONFIG_DIRS") {
-        Ok(paths) => {
-            let mut paths: Vec<PathBuf> = paths.split(':').map(PathBuf::from).collect();
-            paths.dedup();
-            paths
+/// The XDG base directory variables with the generic locations they define.
+const XDG_BASE_VARS: [(&str, LocationType); 6] = [
+    ("XDG_CONFIG_HOME", LocationType::GenericConfigLocation),
+    ("XDG_DATA_HOME", LocationType::GenericDataLocation),
+    ("XDG_CACHE_HOME", LocationType::GenericCacheLocation),
+    ("XDG_STATE_HOME", LocationType::GenericStateLocation),
+    ("XDG_BIN_HOME", LocationType::UserExecutablesLocation),
+    ("XDG_RUNTIME_DIR", LocationType::RuntimeLocation),
+];
+
+/// The XDG base directory lists with the generic locations they define.
+const XDG_DIRS_VARS: [(&str, LocationType); 2] = [
+    ("XDG_CONFIG_DIRS", LocationType::GenericConfigLocation),
+    ("XDG_DATA_DIRS", LocationType::GenericDataLocation),
+];
+
+/// Checks the base directory `value` according to the `strictness`.
+///
+/// Returns the path to use or the reason the value is ignored.
+fn check_base_dir(
+    env: &dyn Environment,
+    strictness: Strictness,
+    value: &OsStr,
+) -> Result<PathBuf, &'static str> {
+    if value.is_empty() {
+        return Err("which is empty");
+    }
+    let path = PathBuf::from(value);
+    match strictness {
+        Strictness::Strict if path.is_relative() => Err("which is not an absolute path"),
+        Strictness::Strict => Ok(path),
+        Strictness::Lenient => match (path.strip_prefix("~"), env.home_dir()) {
+            (Ok(rest), Some(home)) => Ok(home.join(rest)),
+            _ => Ok(path),
+        },
+    }
+}
+
+/// Returns the value of the base directory variable `name`
+/// or [`None`] if it's unset or ignored.
+fn xdg_base_dir(env: &dyn Environment, strictness: Strictness, name: &str) -> Option<PathBuf> {
+    check_base_dir(env, strictness, &env.var_os(name)?).ok()
+}
+
+/// Returns the entries of the base directories list variable `name` which
+/// are not ignored or [`None`] if it's unset or has no valid entries.
+fn xdg_dirs_list(
+    env: &dyn Environment,
+    strictness: Strictness,
+    name: &str,
+) -> Option<Vec<PathBuf>> {
+    let value = env.var_os(name)?;
+    let mut paths: Vec<PathBuf> = std::env::split_paths(&value)
+        .filter_map(|path| check_base_dir(env, strictness, path.as_os_str()).ok())
+        .collect();
+    paths.dedup();
+    if paths.is_empty() {
+        None
+    } else {
+        Some(paths)
+    }
+}
+
+/// Checks the runtime directory `path` meets the security requirements and corrects
+/// its permissions to 0700 if needed.
+///
+/// The checks are performed on the opened directory, so it cannot be replaced in between.
+/// If `follow` is `false` the directory is not allowed to be a symbolic link.
+fn secure_runtime_dir(path: &Path, uid: u32, follow: bool) -> Result<(), Error> {
+    let insecure = |issue| Error::InsecureRuntimeDir {
+        path: path.into(),
+        issue,
+    };
+    let mut flags = libc::O_DIRECTORY;
+    if !follow {
+        // Opening a symbolic link with O_DIRECTORY fails with ENOTDIR,
+        // so detect it beforehand to report the right issue
+        if let Ok(md) = fs::symlink_metadata(path) {
+            if md.file_type().is_symlink() {
+                return Err(insecure(RuntimeDirIssue::Symlink));
+            }
+        }
+        flags |= libc::O_NOFOLLOW;
+    }
+    let dir = match fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(flags)
+        .open(path)
+    {
+        Ok(dir) => dir,
+        Err(err) => {
+            return Err(match err.raw_os_error() {
+                Some(libc::ELOOP) => insecure(RuntimeDirIssue::Symlink),
+                Some(libc::ENOTDIR) => insecure(RuntimeDirIssue::NotADirectory),
+                _ => Error::io(LocationType::RuntimeLocation, path, err),
+            })
         }
+    };
+    let md = dir
+        .metadata()
+        .map_err(|err| Error::io(LocationType::RuntimeLocation, path, err))?;
+
+    // The directory MUST be owned by the user
+    if md.st_uid() != uid {
+        return Err(insecure(RuntimeDirIssue::WrongOwner {
+            owner: md.st_uid(),
+            expected: uid,
+        }));
+    }
+    // And its Unix access mode MUST be 0700.
+    let mode = md.permissions().mode() & 0o7777;
+    if mode != 0o700 {
+        dir.set_permissions(fs::Permissions::from_mode(0o700))
+            .map_err(|_| insecure(RuntimeDirIssue::BadPermissions { mode }))?;
+    }
+    Ok(())
+}
+
+fn xdg_config_dirs(env: &dyn Environment, strictness: Strictness) -> Vec<PathBuf> {
+    // http://standards.freedesktop.org/basedir-spec/latest/
+    match xdg_dirs_list(env, strictness, "XDG_CONFIG_DIRS") {
+        Some(paths) => paths,
         _ => vec!["/etc/xdg".into()],
     }
 }
 
-fn xdg_data_dirs() -> Vec<PathBuf> {
+fn xdg_data_dirs(
+    env: &dyn Environment,
+    strictness: Strictness,
+    fs: &dyn FileSystem,
+) -> Vec<PathBuf> {
     // http://standards.freedesktop.org/basedir-spec/latest/
-    match env::var("XDG_DATA_DIRS") {
-        Ok(paths) => {
-            let mut paths: Vec<PathBuf> = paths.split(':').map(PathBuf::from).collect();
-            paths.dedup();
+    match xdg_dirs_list(env, strictness, "XDG_DATA_DIRS") {
+        Some(paths) => {
             let mut res = Vec::new();
             for path in paths {
-                if path.is_dir() {
-                    let path_str = path.to_str().unwrap();
-                    if !path_str.is_empty() && path_str.starts_with('/') {
-                        res.push(path.canonicalize().unwrap());
+                if fs.is_dir(&path) {
+                    if let Ok(path) = fs.canonicalize(&path) {
+                        res.push(path);
                     }
                 }
             }
@@ -60,15 +186,141 @@ fn xdg_data_dirs() -> Vec<PathBuf> {
     }
 }
 
+/// Returns the path of the `user-dirs.dirs` file.
+pub(crate) fn user_dirs_file(
+    env: &dyn Environment,
+    strictness: Strictness,
+    location: LocationType,
+) -> Result<PathBuf, Error> {
+    let mut path = get_var_or_home!(env, strictness, location, "XDG_CONFIG_HOME", ".config");
+    path.push("user-dirs.dirs");
+    Ok(path)
+}
+
+/// Reads the path of the user directory `location` from the `config` file.
+///
+/// The malformed lines of the file are skipped unless the `strictness` is strict.
+fn user_dir_from_config(
+    env: &dyn Environment,
+    strictness: Strictness,
+    fs: &dyn FileSystem,
+    config: &Path,
+    location: LocationType,
+) -> Result<Option<PathBuf>, Error> {
+    let dirs = UserDirs::load_from(fs, config.into(), strictness)?;
+    match dirs.get_location(location) {
+        Some(entry) if entry.is_home_relative() => match env.home_dir() {
+            Some(home) => Ok(Some(entry.resolve(home))),
+            _ => Err(Error::HomeNotFound { location }),
+        },
+        Some(entry) => Ok(Some(entry.path().into())),
+        _ => Ok(None),
+    }
+}
+
+/// Resolves the default path of the user directory `location` as xdg-user-dirs
+/// would create it.
+///
+/// The name is taken from the system `user-dirs.defaults` file unless it's disabled
+/// by `user-dirs.conf` and falls back to the built-in `name`. It's translated into
+/// the locale from the `user-dirs.locale` file or the messages locale.
+///
+/// The `user-dirs.conf` and `user-dirs.locale` files are looked up next to the user's
+/// `config`, the `user-dirs.conf` and `user-dirs.defaults` files are also looked up
+/// in the `XDG_CONFIG_DIRS`.
+fn default_user_dir(
+    env: &dyn Environment,
+    strictness: Strictness,
+    fs: &dyn FileSystem,
+    config: &Path,
+    location: LocationType,
+    name: &str,
+    home: &Path,
+) -> Result<PathBuf, Error> {
+    let config_dirs = xdg_config_dirs(env, strictness);
+    let conf_path = std::iter::once(config.with_file_name("user-dirs.conf"))
+        .chain(config_dirs.iter().map(|dir| dir.join("user-dirs.conf")))
+        .find(|path| fs.is_file(path));
+    let conf = match conf_path {
+        Some(path) => skip_error(UserDirsConfig::load_from(fs, path, strictness), strictness)?,
+        _ => None,
+    };
+    let conf = conf.unwrap_or_default();
+
+    let mut entry = None;
+    if conf.is_enabled() {
+        let defaults_path = config_dirs
+            .iter()
+            .map(|dir| dir.join("user-dirs.defaults"))
+            .find(|path| fs.is_file(path));
+        if let Some(path) = defaults_path {
+            let defaults = UserDirDefaults::load_from(fs, path, strictness);
+            if let Some(defaults) = skip_error(defaults, strictness)? {
+                entry = defaults.get_location(location).cloned();
+            }
+        }
+    }
+    let relative = match entry {
+        Some(entry) if !entry.is_home_relative() => return Ok(entry.path().into()),
+        Some(entry) => entry.path().to_path_buf(),
+        _ => PathBuf::from(name),
+    };
+
+    let locale = fs
+        .read(&config.with_file_name("user-dirs.locale"))
+        .map(|content| String::from_utf8_lossy(&content).trim().to_string())
+        .ok()
+        .filter(|locale| !locale.is_empty())
+        .or_else(|| messages_locale(env));
+    let translated = translate_user_dir_path(&relative, locale.as_deref().unwrap_or("C"));
+    // The non-ASCII names cannot be converted into a non-UTF-8 filename encoding
+    if conf.is_utf8(locale_codeset(env).as_deref()) || translated.to_string_lossy().is_ascii() {
+        Ok(home.join(translated))
+    } else {
+        Ok(home.join(relative))
+    }
+}
+
+/// Returns the result of reading a system configuration file
+/// or [`None`] if it failed and the `strictness` is lenient.
+fn skip_error<T>(res: Result<T, Error>, strictness: Strictness) -> Result<Option<T>, Error> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if strictness == Strictness::Strict => Err(err),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Returns the codeset of the current locale, e.g. `UTF-8` for `en_US.UTF-8`.
+fn locale_codeset(env: &dyn Environment) -> Option<String> {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env.var(name))
+        .find(|value| !value.is_empty())?;
+    let (_, codeset) = locale.split_once('.')?;
+    Some(codeset.split('@').next().unwrap_or_default().to_string())
+}
+
 impl StandardPaths {
     #[inline]
     pub(super) fn writable_location_impl(&self, location: LocationType) -> Result<PathBuf, Error> {
+        let env = self.sandboxed_environment();
         match location {
-            LocationType::HomeLocation => home::home_dir().ok_or_else(StandardPaths::home_dir_err),
-            LocationType::TempLocation => Ok(env::temp_dir()),
+            LocationType::HomeLocation => env.home_dir().ok_or(Error::HomeNotFound { location }),
+            LocationType::TempLocation => Ok(env.temp_dir()),
             LocationType::AppCacheLocation | LocationType::GenericCacheLocation => {
                 // http://standards.freedesktop.org/basedir-spec/basedir-spec-0.6.html
-                let mut path = get_var_or_home!("XDG_CACHE_HOME", ".cache");
+                // The derived locations follow the override of the generic one
+                let mut path = match self.location_override(LocationType::GenericCacheLocation) {
+                    Some(path) => path,
+                    _ => get_var_or_home!(
+                        &env,
+                        self.strictness,
+                        location,
+                        "XDG_CACHE_HOME",
+                        ".cache"
+                    ),
+                };
                 if location == LocationType::AppCacheLocation {
                     self.append_organization_and_app(&mut path);
                 }
@@ -78,7 +330,17 @@ impl StandardPaths {
             LocationType::AppDataLocation
             | LocationType::AppLocalDataLocation
             | LocationType::GenericDataLocation => {
-                let mut path = get_var_or_home!("XDG_DATA_HOME", ".local", "share");
+                let mut path = match self.location_override(LocationType::GenericDataLocation) {
+                    Some(path) => path,
+                    _ => get_var_or_home!(
+                        &env,
+                        self.strictness,
+                        location,
+                        "XDG_DATA_HOME",
+                        ".local",
+                        "share"
+                    ),
+                };
                 if location == LocationType::AppDataLocation
                     || location == LocationType::AppLocalDataLocation
                 {
@@ -91,67 +353,114 @@ impl StandardPaths {
             | LocationType::GenericConfigLocation
             | LocationType::AppConfigLocation => {
                 // http://standards.freedesktop.org/basedir-spec/latest/
-                let mut path = get_var_or_home!("XDG_CONFIG_HOME", ".config");
+                let mut path = match self.location_override(LocationType::GenericConfigLocation) {
+                    Some(path) => path,
+                    _ => get_var_or_home!(
+                        &env,
+                        self.strictness,
+                        location,
+                        "XDG_CONFIG_HOME",
+                        ".config"
+                    ),
+                };
                 if location == LocationType::AppConfigLocation {
                     self.append_organization_and_app(&mut path);
                 }
                 Ok(path)
             }
 
+            LocationType::GenericStateLocation | LocationType::AppStateLocation => {
+                // http://standards.freedesktop.org/basedir-spec/latest/
+                let mut path = match self.location_override(LocationType::GenericStateLocation) {
+                    Some(path) => path,
+                    _ => get_var_or_home!(
+                        &env,
+                        self.strictness,
+                        location,
+                        "XDG_STATE_HOME",
+                        ".local",
+                        "state"
+                    ),
+                };
+                if location == LocationType::AppStateLocation {
+                    self.append_organization_and_app(&mut path);
+                }
+                Ok(path)
+            }
+
+            LocationType::UserExecutablesLocation => {
+                // https://www.freedesktop.org/software/systemd/man/file-hierarchy.html
+                Ok(get_var_or_home!(
+                    &env,
+                    self.strictness,
+                    location,
+                    "XDG_BIN_HOME",
+                    ".local",
+                    "bin"
+                ))
+            }
+
             LocationType::RuntimeLocation => {
                 // http://standards.freedesktop.org/basedir-spec/latest/
-                let uid = nix::unistd::geteuid();
-                let user_id = uid.as_raw();
-                let (path, md) = match env::var("XDG_RUNTIME_DIR") {
-                    Ok(path) => {
-                        let md = fs::metadata(&path)?;
+                if let Some(root) = env.root() {
+                    let path = root.join("runtime");
+                    fs::DirBuilder::new()
+                        .recursive(true)
+                        .mode(0o700)
+                        .create(&path)
+                        .map_err(|err| Error::io(location, &path, err))?;
+                }
+                let user_id = match env.uid() {
+                    Some(uid) => uid,
+                    _ => {
+                        return Err(Error::UserNotFound {
+                            location,
+                            reason: "Failed to detect current user id".into(),
+                        })
+                    }
+                };
+                let path = match xdg_base_dir(&env, self.strictness, "XDG_RUNTIME_DIR") {
+                    Some(path) => {
+                        let md =
+                            fs::metadata(&path).map_err(|err| Error::io(location, &path, err))?;
                         if !md.is_dir() {
-                            return Err(Error::new(
-                                ErrorKind::Other,
-                                format!(
-                                    "'XDG_RUNTIME_DIR' points to '{path}' which is not a directory"
-                                ),
-                            ));
+                            return Err(Error::InvalidEnvVar {
+                                location,
+                                name: "XDG_RUNTIME_DIR".into(),
+                                value: path,
+                                reason: "which is not a directory".into(),
+                            });
                         }
-                        (PathBuf::from(path), md)
+                        secure_runtime_dir(&path, user_id, true)?;
+                        path
                     }
                     _ => {
-                        let user = match nix::unistd::User::from_uid(uid) {
-                            Ok(opt) => opt.unwrap(),
-                            Err(err) => return Err(Error::new(
-                                ErrorKind::NotFound,
-                                format!("Failed to detect current user: {err}"),
-                            ))
+                        let user_name = match env.user_name() {
+                            Ok(name) => name,
+                            Err(err) => {
+                                return Err(Error::UserNotFound {
+                                    location,
+                                    reason: err.to_string(),
+                                })
+                            }
                         };
                         let mut runtime_dir = String::from("runtime-");
-                        runtime_dir.push_str(&user.name);
-                        let mut path = env::temp_dir();
+                        runtime_dir.push_str(&user_name);
+                        let mut path = env.temp_dir();
                         path.push(runtime_dir);
-                        let md = fs::metadata(&path)?;
-                        if !md.is_dir() {
-                            fs::create_dir_all(&path)?;
+                        // Create the directory atomically with the right mode, so there is
+                        // no window in which it's accessible by the others.
+                        match fs::DirBuilder::new().mode(0o700).create(&path) {
+                            Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+                                return Err(Error::io(location, &path, err))
+                            }
+                            _ => {}
                         }
-                        (path, md)
+                        secure_runtime_dir(&path, user_id, false)?;
+                        path
                     }
                 };
 
-                // The directory MUST be owned by the user
-                let ts_uid = md.st_uid();
-                if ts_uid != user_id {
-                    return Err(Error::new(
-                        ErrorKind::PermissionDenied,
-                        format!(
-                            "Wrong ownership on runtime directory '{}' - {ts_uid} instead of {user_id}",
-                            path.to_string_lossy(),
-                        ),
-                    ));
-                }
-                // And its Unix access mode MUST be 0700.
-                let mut permissions = md.permissions();
-                if permissions.mode() != 0o40700 {
-                    permissions.set_mode(0o40700);
-                }
-
                 Ok(path)
             }
 
@@ -161,72 +470,92 @@ impl StandardPaths {
                 } else {
                     "applications"
                 };
-                let mut path = self.writable_location_impl(LocationType::GenericDataLocation)?;
+                let mut path = self.writable_location(LocationType::GenericDataLocation)?;
                 path.push(dir);
                 Ok(path)
             }
 
             _ => {
                 // http://www.freedesktop.org/wiki/Software/xdg-user-dirs
-                let mut config = get_var_or_home!("XDG_CONFIG_HOME", ".config");
-                config.push("user-dirs.dirs");
-                let file = fs::File::open(&config)?;
-                let buf_reader = BufReader::new(file);
-                let mut lines = HashMap::new();
-                for line in buf_reader.lines() {
-                    let line = line.unwrap();
-                    if line.starts_with("XDG") {
-                        let parts = line.split('=').collect::<Vec<&str>>();
-                        let key = parts[0];
-                        let key = parts[0].get(4..key.len() - 4).unwrap();
-                        let mut value = parts[1];
-                        if value.len() > 2 && value.starts_with('"') && value.ends_with('"') {
-                            value = value.get(1..value.len() - 1).unwrap();
-                        }
-                        lines.insert(key.to_string(), value.to_string());
-                    }
-                }
-
-                let key = match location {
-                    LocationType::DesktopLocation => "DESKTOP",
-                    LocationType::DocumentsLocation => "DOCUMENTS",
-                    LocationType::PicturesLocation => "PICTURES",
-                    LocationType::MusicLocation => "MUSIC",
-                    LocationType::MoviesLocation => "VIDEOS",
-                    LocationType::DownloadLocation => "DOWNLOAD",
-                    _ => "",
+                let dir = match USER_DIRS.iter().find(|(loc, ..)| *loc == location) {
+                    Some((_, _, dir)) => *dir,
+                    _ => return Err(Error::UnsupportedLocation(location)),
                 };
-                if lines.contains_key(key) {
-                    let value = &lines[key];
-                    if value.starts_with("$HOME") {
-                        let mut path = match home::home_dir() {
-                            Some(path) => path,
-                            _ => return Err(StandardPaths::home_dir_err()),
-                        };
-                        let value = value.get(6..).unwrap();
-                        path.push(value);
+                let config = user_dirs_file(&env, self.strictness, location)?;
+                if self.fs.exists(&config) {
+                    if let Some(path) = user_dir_from_config(
+                        &env,
+                        self.strictness,
+                        self.fs.as_ref(),
+                        &config,
+                        location,
+                    )? {
                         return Ok(path);
                     }
-                    return Ok(value.into());
                 }
 
-                let dir = match location {
-                    LocationType::DesktopLocation => "Desktop",
-                    LocationType::DocumentsLocation => "Documents",
-                    LocationType::PicturesLocation => "Pictures",
-                    LocationType::MusicLocation => "Music",
-                    LocationType::MoviesLocation => "Videos",
-                    LocationType::DownloadLocation => "Downloads",
-                    _ => return Err(Error::new(ErrorKind::Other, "Unexpected error")),
-                };
-                let mut path = match home::home_dir() {
+                let home = match env.home_dir() {
                     Some(path) => path,
-                    _ => return Err(StandardPaths::home_dir_err()),
+                    _ => return Err(Error::HomeNotFound { location }),
                 };
-                path.push(dir);
-                Ok(path)
+                // The sandbox does not depend on the system defaults and the locale
+                if env.root().is_some() {
+                    return Ok(home.join(dir));
+                }
+                default_user_dir(
+                    &env,
+                    self.strictness,
+                    self.fs.as_ref(),
+                    &config,
+                    location,
+                    dir,
+                    &home,
+                )
+            }
+        }
+    }
+
+    /// Returns the path of the user directory `location` configured in the
+    /// `user-dirs.dirs` file or [`None`] if the file does not exist or
+    /// does not define the directory.
+    pub(super) fn configured_user_dir(
+        &self,
+        location: LocationType,
+    ) -> Result<Option<PathBuf>, Error> {
+        let env = self.sandboxed_environment();
+        let config = user_dirs_file(&env, self.strictness, location)?;
+        if !self.fs.exists(&config) {
+            return Ok(None);
+        }
+        user_dir_from_config(&env, self.strictness, self.fs.as_ref(), &config, location)
+    }
+
+    pub(super) fn validate_environment_impl(&self) -> Vec<Error> {
+        let env = self.sandboxed_environment();
+        let mut res = Vec::new();
+        let mut check = |name: &str, location: LocationType, value: &OsStr| {
+            if let Err(reason) = check_base_dir(&env, self.strictness, value) {
+                res.push(Error::InvalidEnvVar {
+                    location,
+                    name: name.into(),
+                    value: value.into(),
+                    reason: reason.into(),
+                });
+            }
+        };
+        for (name, location) in XDG_BASE_VARS {
+            if let Some(value) = env.var_os(name) {
+                check(name, location, &value);
             }
         }
+        for (name, location) in XDG_DIRS_VARS {
+            if let Some(value) = env.var_os(name) {
+                for path in std::env::split_paths(&value) {
+                    check(name, location, path.as_os_str());
+                }
+            }
+        }
+        res
     }
 
     #[inline]
@@ -234,20 +563,25 @@ impl StandardPaths {
         &self,
         location: LocationType,
     ) -> Result<Vec<PathBuf>, Error> {
+        let env = self.sandboxed_environment();
         let mut res: Vec<PathBuf> = match location {
-            LocationType::ConfigLocation | LocationType::GenericConfigLocation => xdg_config_dirs(),
+            LocationType::ConfigLocation | LocationType::GenericConfigLocation => {
+                xdg_config_dirs(&env, self.strictness)
+            }
             LocationType::AppConfigLocation => {
-                let mut dirs = xdg_config_dirs();
+                let mut dirs = xdg_config_dirs(&env, self.strictness);
                 for dir in dirs.iter_mut() {
                     self.append_organization_and_app(dir);
                 }
                 dirs
             }
 
-            LocationType::GenericDataLocation => xdg_data_dirs(),
+            LocationType::GenericDataLocation => {
+                xdg_data_dirs(&env, self.strictness, self.file_system())
+            }
 
             LocationType::ApplicationsLocation => {
-                let mut dirs = xdg_data_dirs();
+                let mut dirs = xdg_data_dirs(&env, self.strictness, self.file_system());
                 for dir in dirs.iter_mut() {
                     dir.push("applications");
                 }
@@ -255,19 +589,19 @@ impl StandardPaths {
             }
 
             LocationType::AppDataLocation | LocationType::AppLocalDataLocation => {
-                let mut dirs = xdg_data_dirs();
+                let mut dirs = xdg_data_dirs(&env, self.strictness, self.file_system());
                 for dir in dirs.iter_mut() {
                     self.append_organization_and_app(dir);
                 }
                 dirs
             }
 
-            LocationType::FontsLocation => match home::home_dir() {
+            LocationType::FontsLocation => match env.home_dir() {
                 Some(mut path) => {
                     path.push(".fonts");
                     vec![path]
                 }
-                _ => return Err(StandardPaths::home_dir_err()),
+                _ => return Err(Error::HomeNotFound { location }),
             },
 
             _ => Vec::new(),
@@ -280,23 +614,27 @@ impl StandardPaths {
     }
 }
 
-/// Detect if `path` is an executable based on its rights
-fn is_executable<P>(path: P) -> bool
-where
-    P: Into<PathBuf>,
-{
-    let path = path.into();
-    match fs::metadata(path) {
-        Ok(md) => md.permissions().mode() & 0o111 != 0,
-        _ => false,
-    }
+/// Checks the `mode` access of the current user to the entry at `path`
+/// with `access(2)`, e.g. `libc::W_OK`.
+pub(crate) fn has_access(path: &Path, mode: libc::c_int) -> bool {
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        _ => return false,
+    };
+    // SAFETY: the path is a valid nul-terminated string
+    unsafe { libc::access(path.as_ptr(), mode) == 0 }
 }
 
 const EXTENSIONS: [&str; 3] = ["bin", "run", "sh"];
 
 #[inline]
-pub(super) fn find_executable_in_paths_impl<S, P>(name: S, paths: P) -> Option<Vec<PathBuf>>
+pub(super) fn find_executable_in_paths_impl<F, S, P>(
+    fs: &F,
+    name: S,
+    paths: P,
+) -> Option<Vec<PathBuf>>
 where
+    F: FileSystem + ?Sized,
     S: Into<String>,
     P: AsRef<Vec<PathBuf>>,
 {
@@ -304,17 +642,20 @@ where
     let path = PathBuf::from(&name);
 
     // Check absolute paths
-    if path.is_absolute() && is_executable(&name) {
+    if path.is_absolute() && fs.is_executable(&path) {
         return Some(vec![path]);
     }
 
     // Check paths
     let mut paths = paths.as_ref().clone();
-    paths.retain(|p| !p.to_str().unwrap().is_empty() && p.is_dir());
+    paths.retain(|p| !p.as_os_str().is_empty() && fs.is_dir(p));
     let paths = {
         let mut paths2 = Vec::new();
         for path in paths {
-            let path = path.canonicalize().unwrap();
+            let path = match fs.canonicalize(&path) {
+                Ok(path) => path,
+                _ => continue,
+            };
             if !paths2.contains(&path) {
                 paths2.push(path);
             }
@@ -326,7 +667,7 @@ where
     let mut res = Vec::new();
     for mut path in paths.iter().cloned() {
         path.push(&name);
-        if is_executable(&path) {
+        if fs.is_executable(&path) {
             res.push(path);
         }
     }
@@ -338,7 +679,7 @@ where
             for ext in &EXTENSIONS {
                 let mut full_path = path.clone();
                 full_path.set_extension(ext);
-                if is_executable(&full_path) {
+                if fs.is_executable(&full_path) {
                     res.push(full_path);
                 }
             }
@@ -351,3 +692,512 @@ where
         Some(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TempDir, MapEnvironment, MemoryFileSystem};
+
+    fn paths(env: MapEnvironment) -> StandardPaths {
+        StandardPaths::builder()
+            .app_name("app")
+            .org_name("org")
+            .environment(env.with_home("/home/user"))
+            .build()
+    }
+
+    #[test]
+    fn state_locations() {
+        let sp = paths(MapEnvironment::new());
+        assert_eq!(
+            sp.writable_location(LocationType::GenericStateLocation)
+                .unwrap(),
+            PathBuf::from("/home/user/.local/state")
+        );
+        assert_eq!(
+            sp.writable_location(LocationType::AppStateLocation)
+                .unwrap(),
+            PathBuf::from("/home/user/.local/state/org/app")
+        );
+        assert_eq!(
+            sp.standard_locations(LocationType::AppStateLocation)
+                .unwrap(),
+            [PathBuf::from("/home/user/.local/state/org/app")]
+        );
+
+        let sp = paths(MapEnvironment::new().with_var("XDG_STATE_HOME", "/var/state"));
+        assert_eq!(
+            sp.writable_location(LocationType::AppStateLocation)
+                .unwrap(),
+            PathBuf::from("/var/state/org/app")
+        );
+
+        let sp = StandardPaths::builder()
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("XDG_STATE_HOME", "state"),
+            )
+            .strictness(Strictness::Strict)
+            .build();
+        assert_eq!(
+            sp.writable_location(LocationType::GenericStateLocation)
+                .unwrap(),
+            PathBuf::from("/home/user/.local/state")
+        );
+    }
+
+    #[test]
+    fn state_locations_in_test_mode() {
+        let sp = StandardPaths::builder()
+            .app_name("app")
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("XDG_STATE_HOME", "/var/state"),
+            )
+            .test_root("/sandbox")
+            .file_system(MemoryFileSystem::new())
+            .build();
+        assert_eq!(
+            sp.writable_location(LocationType::AppStateLocation)
+                .unwrap(),
+            PathBuf::from("/sandbox/state/app")
+        );
+    }
+
+    #[test]
+    fn template_and_public_share_locations() {
+        let home = TempDir::new();
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home(home.path()))
+            .build();
+        assert_eq!(
+            sp.writable_location(LocationType::TemplatesLocation)
+                .unwrap(),
+            home.join("Templates")
+        );
+        assert_eq!(
+            sp.writable_location(LocationType::PublicShareLocation)
+                .unwrap(),
+            home.join("Public")
+        );
+
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::write(
+            home.join(".config/user-dirs.dirs"),
+            "XDG_TEMPLATES_DIR=\"$HOME/Vorlagen\"\nXDG_PUBLICSHARE_DIR=\"/srv/public\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            sp.writable_location(LocationType::TemplatesLocation)
+                .unwrap(),
+            home.join("Vorlagen")
+        );
+        assert_eq!(
+            sp.standard_locations(LocationType::PublicShareLocation)
+                .unwrap(),
+            [PathBuf::from("/srv/public")]
+        );
+    }
+
+    #[test]
+    fn user_executables_location() {
+        let sp = paths(MapEnvironment::new());
+        assert_eq!(
+            sp.writable_location(LocationType::UserExecutablesLocation)
+                .unwrap(),
+            PathBuf::from("/home/user/.local/bin")
+        );
+
+        let sp = paths(MapEnvironment::new().with_var("XDG_BIN_HOME", "/opt/bin"));
+        assert_eq!(
+            sp.standard_locations(LocationType::UserExecutablesLocation)
+                .unwrap(),
+            [PathBuf::from("/opt/bin")]
+        );
+    }
+
+    #[test]
+    fn user_executables_on_path() {
+        let on_path = |path: &str, fs: MemoryFileSystem| {
+            StandardPaths::builder()
+                .environment(
+                    MapEnvironment::new()
+                        .with_home("/home/user")
+                        .with_var("PATH", path),
+                )
+                .file_system(fs)
+                .build()
+                .is_user_executables_on_path()
+                .unwrap()
+        };
+        assert!(on_path(
+            "/usr/bin:/home/user/.local/bin",
+            MemoryFileSystem::new()
+        ));
+        assert!(!on_path("/usr/bin:/home/user/bin", MemoryFileSystem::new()));
+        assert!(!on_path("", MemoryFileSystem::new()));
+
+        let fs = MemoryFileSystem::new()
+            .with_dir("/home/user/.local/bin")
+            .with_symlink("/home/user/bin", "/home/user/.local/bin");
+        assert!(on_path("/usr/bin:/home/user/bin", fs));
+    }
+
+    #[test]
+    fn malformed_user_dirs_lines() {
+        let home = TempDir::new();
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::write(
+            home.join(".config/user-dirs.dirs"),
+            "stray\nXDG_DESKTOP_DIR=\"$HOME/Schreibtisch\"\n",
+        )
+        .unwrap();
+        let builder =
+            || StandardPaths::builder().environment(MapEnvironment::new().with_home(home.path()));
+
+        let sp = builder().build();
+        assert_eq!(
+            sp.writable_location(LocationType::DesktopLocation).unwrap(),
+            home.join("Schreibtisch")
+        );
+        let sp = builder().strictness(Strictness::Strict).build();
+        assert!(matches!(
+            sp.writable_location(LocationType::DesktopLocation),
+            Err(Error::UserDirsParse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn relative_entries_of_dirs_lists() {
+        let env = MapEnvironment::new()
+            .with_var("XDG_CONFIG_DIRS", "etc/xdg:/etc/xdg")
+            .with_var("XDG_DATA_DIRS", "share:/usr/share");
+        let fs = MemoryFileSystem::new()
+            .with_dir("share")
+            .with_dir("/usr/share");
+
+        assert_eq!(
+            xdg_config_dirs(&env, Strictness::Lenient),
+            [PathBuf::from("etc/xdg"), "/etc/xdg".into()]
+        );
+        assert_eq!(
+            xdg_data_dirs(&env, Strictness::Lenient, &fs),
+            [PathBuf::from("share"), "/usr/share".into()]
+        );
+        assert_eq!(
+            xdg_config_dirs(&env, Strictness::Strict),
+            [PathBuf::from("/etc/xdg")]
+        );
+        assert_eq!(
+            xdg_data_dirs(&env, Strictness::Strict, &fs),
+            [PathBuf::from("/usr/share")]
+        );
+    }
+
+    #[test]
+    fn strictness() {
+        let env = MapEnvironment::new()
+            .with_home("/home/user")
+            .with_var("XDG_CONFIG_HOME", "~/conf")
+            .with_var("XDG_CACHE_HOME", "")
+            .with_var("XDG_DATA_HOME", "data")
+            .with_var("XDG_CONFIG_DIRS", "/etc/a:etc/b::/etc/a:~/etc");
+        let builder = |strictness| {
+            StandardPaths::builder()
+                .environment(env.clone())
+                .strictness(strictness)
+                .file_system(MemoryFileSystem::new())
+                .build()
+        };
+        let writable = |sp: &StandardPaths, location| sp.writable_location(location).unwrap();
+
+        let sp = builder(Strictness::Lenient);
+        assert_eq!(
+            writable(&sp, LocationType::GenericConfigLocation),
+            Path::new("/home/user/conf")
+        );
+        assert_eq!(
+            writable(&sp, LocationType::GenericCacheLocation),
+            Path::new("/home/user/.cache")
+        );
+        assert_eq!(
+            writable(&sp, LocationType::GenericDataLocation),
+            Path::new("data")
+        );
+        assert_eq!(
+            xdg_config_dirs(&env, Strictness::Lenient),
+            [
+                PathBuf::from("/etc/a"),
+                "etc/b".into(),
+                "/etc/a".into(),
+                "/home/user/etc".into()
+            ]
+        );
+        let issues = sp
+            .validate_environment()
+            .iter()
+            .map(|err| match err {
+                Error::InvalidEnvVar { name, reason, .. } => format!("{name} {reason}"),
+                err => panic!("unexpected error: {err:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            [
+                "XDG_CACHE_HOME which is empty",
+                "XDG_CONFIG_DIRS which is empty"
+            ]
+        );
+
+        let sp = builder(Strictness::Strict);
+        assert_eq!(
+            writable(&sp, LocationType::GenericConfigLocation),
+            Path::new("/home/user/.config")
+        );
+        assert_eq!(
+            writable(&sp, LocationType::GenericDataLocation),
+            Path::new("/home/user/.local/share")
+        );
+        assert_eq!(
+            xdg_config_dirs(&env, Strictness::Strict),
+            [PathBuf::from("/etc/a")]
+        );
+        let issues = sp
+            .validate_environment()
+            .into_iter()
+            .map(|err| (err.location().unwrap(), err.path().unwrap().to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            [
+                (LocationType::GenericConfigLocation, PathBuf::from("~/conf")),
+                (LocationType::GenericDataLocation, PathBuf::from("data")),
+                (LocationType::GenericCacheLocation, PathBuf::new()),
+                (LocationType::GenericConfigLocation, PathBuf::from("etc/b")),
+                (LocationType::GenericConfigLocation, PathBuf::new()),
+                (LocationType::GenericConfigLocation, PathBuf::from("~/etc")),
+            ]
+        );
+
+        // Without any valid entries the list falls back to the default
+        let env = MapEnvironment::new().with_var("XDG_CONFIG_DIRS", "etc:");
+        assert_eq!(
+            xdg_config_dirs(&env, Strictness::Strict),
+            [PathBuf::from("/etc/xdg")]
+        );
+    }
+
+    #[test]
+    fn malformed_system_user_dirs_files() {
+        let fs = MemoryFileSystem::new()
+            .with_file_content("/etc/xdg/user-dirs.conf", "enabled=maybe\n")
+            .with_file_content(
+                "/etc/xdg/user-dirs.defaults",
+                b"DESKTOP=Work\n\xff\nMUSIC\n",
+            );
+        let builder = |strictness| {
+            StandardPaths::builder()
+                .environment(MapEnvironment::new().with_home("/home/user"))
+                .file_system(fs.clone())
+                .strictness(strictness)
+                .build()
+        };
+
+        let sp = builder(Strictness::Lenient);
+        assert_eq!(
+            sp.writable_location(LocationType::DesktopLocation).unwrap(),
+            Path::new("/home/user/Work")
+        );
+        assert_eq!(
+            sp.writable_location(LocationType::MusicLocation).unwrap(),
+            Path::new("/home/user/Music")
+        );
+        let sp = builder(Strictness::Strict);
+        assert!(matches!(
+            sp.writable_location(LocationType::DesktopLocation),
+            Err(Error::UserDirsParse { line: 1, .. })
+        ));
+
+        // An unreadable file is skipped too
+        struct Unreadable(MemoryFileSystem);
+
+        impl FileSystem for Unreadable {
+            fn metadata(&self, path: &Path) -> io::Result<crate::Metadata> {
+                self.0.metadata(path)
+            }
+
+            fn symlink_metadata(&self, path: &Path) -> io::Result<crate::Metadata> {
+                self.0.symlink_metadata(path)
+            }
+
+            fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+                self.0.canonicalize(path)
+            }
+        }
+
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home("/home/user"))
+            .file_system(Unreadable(fs.clone()))
+            .build();
+        assert_eq!(
+            sp.writable_location(LocationType::DesktopLocation).unwrap(),
+            Path::new("/home/user/Desktop")
+        );
+    }
+
+    /// Builds the paths resolving the runtime directory in the `temp` directory.
+    fn runtime_paths(temp: &TempDir, uid: u32, runtime_dir: Option<&Path>) -> StandardPaths {
+        let mut env = MapEnvironment::new()
+            .with_temp_dir(temp.path())
+            .with_uid(uid)
+            .with_user_name("user");
+        if let Some(dir) = runtime_dir {
+            env = env.with_var("XDG_RUNTIME_DIR", dir);
+        }
+        StandardPaths::builder().environment(env).build()
+    }
+
+    fn uid() -> u32 {
+        nix::unistd::geteuid().as_raw()
+    }
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    fn runtime_dir_issue(sp: &StandardPaths) -> RuntimeDirIssue {
+        match sp.writable_location(LocationType::RuntimeLocation) {
+            Err(Error::InsecureRuntimeDir { issue, .. }) => issue,
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn runtime_dir_is_created() {
+        let temp = TempDir::new();
+        let sp = runtime_paths(&temp, uid(), None);
+        let path = sp.writable_location(LocationType::RuntimeLocation).unwrap();
+        assert_eq!(path, temp.join("runtime-user"));
+        assert_eq!(mode(&path), 0o700);
+    }
+
+    #[test]
+    fn runtime_dir_mode_is_fixed() {
+        let temp = TempDir::new();
+        let fallback = temp.join("runtime-user");
+        fs::create_dir(&fallback).unwrap();
+        fs::set_permissions(&fallback, fs::Permissions::from_mode(0o755)).unwrap();
+        let sp = runtime_paths(&temp, uid(), None);
+        assert_eq!(
+            sp.writable_location(LocationType::RuntimeLocation).unwrap(),
+            fallback
+        );
+        assert_eq!(mode(&fallback), 0o700);
+
+        let dir = temp.join("xdg");
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o711)).unwrap();
+        let sp = runtime_paths(&temp, uid(), Some(&dir));
+        assert_eq!(
+            sp.writable_location(LocationType::RuntimeLocation).unwrap(),
+            dir
+        );
+        assert_eq!(mode(&dir), 0o700);
+    }
+
+    #[test]
+    fn runtime_dir_symlink() {
+        let temp = TempDir::new();
+        let target = temp.join("target");
+        fs::DirBuilder::new().mode(0o700).create(&target).unwrap();
+        std::os::unix::fs::symlink(&target, temp.join("runtime-user")).unwrap();
+
+        // The fallback directory must not be a symbolic link
+        let sp = runtime_paths(&temp, uid(), None);
+        assert_eq!(runtime_dir_issue(&sp), RuntimeDirIssue::Symlink);
+
+        // The directory from the environment is trusted to be one
+        let sp = runtime_paths(&temp, uid(), Some(&temp.join("runtime-user")));
+        assert_eq!(
+            sp.writable_location(LocationType::RuntimeLocation).unwrap(),
+            temp.join("runtime-user")
+        );
+    }
+
+    #[test]
+    fn runtime_dir_wrong_owner() {
+        let temp = TempDir::new();
+        // The directory is owned by the real user, not the one of the environment
+        let sp = runtime_paths(&temp, uid() + 1, None);
+        assert_eq!(
+            runtime_dir_issue(&sp),
+            RuntimeDirIssue::WrongOwner {
+                owner: uid(),
+                expected: uid() + 1,
+            }
+        );
+    }
+
+    #[test]
+    fn runtime_dir_not_a_directory() {
+        let temp = TempDir::new();
+        fs::write(temp.join("runtime-user"), "").unwrap();
+        let sp = runtime_paths(&temp, uid(), None);
+        assert_eq!(runtime_dir_issue(&sp), RuntimeDirIssue::NotADirectory);
+
+        let sp = runtime_paths(&temp, uid(), Some(&temp.join("runtime-user")));
+        assert!(matches!(
+            sp.writable_location(LocationType::RuntimeLocation),
+            Err(Error::InvalidEnvVar { .. })
+        ));
+    }
+
+    #[test]
+    fn user_dirs_from_file_system() {
+        let fs = MemoryFileSystem::new()
+            .with_file_content(
+                "/home/user/.config/user-dirs.dirs",
+                "XDG_DESKTOP_DIR=\"$HOME/Work\"\n",
+            )
+            .with_file_content("/home/user/.config/user-dirs.locale", "de_DE\n")
+            .with_file_content(
+                "/etc/xdg/user-dirs.defaults",
+                "DOWNLOAD=Desktop/Downloads\nMUSIC=/srv/music\n",
+            );
+        let builder = |fs| {
+            StandardPaths::builder()
+                .environment(MapEnvironment::new().with_home("/home/user"))
+                .file_system(fs)
+                .build()
+        };
+
+        let sp = builder(fs.clone());
+        let writable = |location| sp.writable_location(location).unwrap();
+        assert_eq!(
+            writable(LocationType::DesktopLocation),
+            Path::new("/home/user/Work")
+        );
+        assert_eq!(
+            writable(LocationType::DownloadLocation),
+            Path::new("/home/user/Schreibtisch/Downloads")
+        );
+        assert_eq!(
+            writable(LocationType::MusicLocation),
+            Path::new("/srv/music")
+        );
+        assert_eq!(
+            writable(LocationType::PicturesLocation),
+            Path::new("/home/user/Bilder")
+        );
+
+        // The system defaults are ignored if disabled
+        let sp =
+            builder(fs.with_file_content("/home/user/.config/user-dirs.conf", "enabled=False\n"));
+        assert_eq!(
+            sp.writable_location(LocationType::MusicLocation).unwrap(),
+            Path::new("/home/user/Musik")
+        );
+    }
+}
@@ -0,0 +1,538 @@
+use std::{
+    error, fmt,
+    io::{self, ErrorKind},
+    iter::FusedIterator,
+    ops::{BitAnd, BitOr, BitOrAssign},
+    path::{Path, PathBuf},
+    str::FromStr,
+    vec,
+};
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Error, FileSystem, LocationType, StandardPaths};
+
+/// A combination of the locate flags.
+///
+/// Is used to call
+/// [`StandardPaths::locate`],
+/// [`StandardPaths::locate_all`] and
+/// [`StandardPaths::locate_iter`].
+///
+/// The entry type flags ([`LocateOption::LocateFile`], [`LocateOption::LocateDirectory`]
+/// and [`LocateOption::LocateSymlink`]) are alternatives, an entry matches if it's
+/// of any of the requested types. If none of them or exactly [`LocateOption::LocateBoth`]
+/// is requested, an existing entry of any type matches, including sockets, FIFOs
+/// and device nodes.
+/// The access flags ([`LocateOption::Readable`], [`LocateOption::Writable`]
+/// and [`LocateOption::Executable`]) are requirements checked for the current user.
+/// They always apply to the target of a symbolic link, even with [`LocateOption::NoFollow`]
+/// or [`LocateOption::LocateSymlink`], since the permissions of a link itself are not used
+/// by the system. So a dangling link is neither readable, writable nor executable.
+///
+/// ### Example
+/// ```
+/// use standard_paths::LocateOption;
+///
+/// let option = LocateOption::LocateFile | LocateOption::Readable | LocateOption::Executable;
+/// assert!(option.contains(LocateOption::LocateFile));
+/// assert!(!option.contains(LocateOption::LocateBoth));
+/// assert_eq!(option.to_string(), "LocateFile | Readable | Executable");
+/// assert_eq!("LocateFile | Readable | Executable".parse(), Ok(option));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LocateOption(u32);
+
+#[allow(non_upper_case_globals)]
+impl LocateOption {
+    /// Locate files.
+    pub const LocateFile: LocateOption = LocateOption(0x01);
+    /// Locate directories.
+    pub const LocateDirectory: LocateOption = LocateOption(0x02);
+    /// Locate existing entries of any type, like files, directories or sockets.
+    pub const LocateBoth: LocateOption = LocateOption(0x03);
+    /// Locate symbolic links themselves. Implies [`LocateOption::NoFollow`].
+    pub const LocateSymlink: LocateOption = LocateOption(0x04);
+    /// Check the entry type without traversing symbolic links.
+    pub const NoFollow: LocateOption = LocateOption(0x08);
+    /// Locate only the entries readable by the current user.
+    pub const Readable: LocateOption = LocateOption(0x10);
+    /// Locate only the entries writable by the current user.
+    pub const Writable: LocateOption = LocateOption(0x20);
+    /// Locate only the executable entries.
+    pub const Executable: LocateOption = LocateOption(0x40);
+}
+
+/// The names of the single flags in the display order.
+const FLAGS: [(LocateOption, &str); 7] = [
+    (LocateOption::LocateFile, "LocateFile"),
+    (LocateOption::LocateDirectory, "LocateDirectory"),
+    (LocateOption::LocateSymlink, "LocateSymlink"),
+    (LocateOption::NoFollow, "NoFollow"),
+    (LocateOption::Readable, "Readable"),
+    (LocateOption::Writable, "Writable"),
+    (LocateOption::Executable, "Executable"),
+];
+
+impl LocateOption {
+    /// Returns the option without flags which matches entries of any type.
+    pub const fn empty() -> LocateOption {
+        LocateOption(0)
+    }
+
+    /// Returns the raw value of the flags.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if no flags are set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all the flags of `other` are set.
+    pub const fn contains(&self, other: LocateOption) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if any of the flags of `other` is set.
+    pub const fn intersects(&self, other: LocateOption) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Checks the entry at `path` against the flags.
+    ///
+    /// The access flags are checked for the target of a symbolic link.
+    ///
+    /// Returns the error of the metadata query, e.g. if the entry does not exist.
+    pub(crate) fn check(self, fs: &dyn FileSystem, path: &Path) -> io::Result<bool> {
+        let md = if self.intersects(LocateOption::NoFollow | LocateOption::LocateSymlink) {
+            fs.symlink_metadata(path)?
+        } else {
+            fs.metadata(path)?
+        };
+        let types = self & (LocateOption::LocateBoth | LocateOption::LocateSymlink);
+        let type_matches = types.is_empty()
+            || types == LocateOption::LocateBoth
+            || (md.is_file() && types.contains(LocateOption::LocateFile))
+            || (md.is_dir() && types.contains(LocateOption::LocateDirectory))
+            || (md.is_symlink() && types.contains(LocateOption::LocateSymlink));
+        Ok(type_matches
+            && (!self.contains(LocateOption::Readable) || fs.is_readable(path))
+            && (!self.contains(LocateOption::Writable) || fs.is_writable(path))
+            && (!self.contains(LocateOption::Executable) || fs.is_executable(path)))
+    }
+}
+
+impl BitOr for LocateOption {
+    type Output = LocateOption;
+
+    fn bitor(self, rhs: LocateOption) -> LocateOption {
+        LocateOption(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for LocateOption {
+    fn bitor_assign(&mut self, rhs: LocateOption) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for LocateOption {
+    type Output = LocateOption;
+
+    fn bitand(self, rhs: LocateOption) -> LocateOption {
+        LocateOption(self.0 & rhs.0)
+    }
+}
+
+impl fmt::Display for LocateOption {
+    /// Formats the flags as their names separated by ` | `,
+    /// with [`LocateOption::LocateBoth`] in place of the both entry types.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = Vec::new();
+        let mut rest = *self;
+        if self.contains(LocateOption::LocateBoth) {
+            names.push("LocateBoth");
+            rest = LocateOption(rest.0 & !LocateOption::LocateBoth.0);
+        }
+        names.extend(
+            FLAGS
+                .iter()
+                .filter(|(flag, _)| rest.contains(*flag))
+                .map(|(_, name)| *name),
+        );
+        f.pad(&names.join(" | "))
+    }
+}
+
+impl fmt::Debug for LocateOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LocateOption({self})")
+    }
+}
+
+/// The error returned when parsing a [`LocateOption`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLocateOptionError {
+    /// The flag name which failed to parse.
+    value: String,
+}
+
+impl ParseLocateOptionError {
+    /// Returns the flag name which failed to parse.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ParseLocateOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bad locate option '{}'", self.value)
+    }
+}
+
+impl error::Error for ParseLocateOptionError {}
+
+impl FromStr for LocateOption {
+    type Err = ParseLocateOptionError;
+
+    /// Parses the flag names separated by `|`, e.g. `LocateFile | Readable`.
+    ///
+    /// The comparison is ASCII case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut res = LocateOption::empty();
+        for name in s.split('|').map(str::trim).filter(|name| !name.is_empty()) {
+            res |= if name.eq_ignore_ascii_case("LocateBoth") {
+                LocateOption::LocateBoth
+            } else {
+                FLAGS
+                    .iter()
+                    .find(|(_, flag)| flag.eq_ignore_ascii_case(name))
+                    .map(|(flag, _)| *flag)
+                    .ok_or_else(|| ParseLocateOptionError {
+                        value: name.to_string(),
+                    })?
+            };
+        }
+        Ok(res)
+    }
+}
+
+/// Is serialized as its [`Display`](fmt::Display) form, so the single values
+/// are compatible with the previous enum representation, e.g. `"LocateFile"`.
+#[cfg(feature = "serde")]
+impl Serialize for LocateOption {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for LocateOption {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// A lazy iterator over the files or directories found in the standard locations.
+///
+/// Is returned by [`StandardPaths::locate_iter`].
+pub struct LocateIter<'a> {
+    /// The filesystem the entries are checked on.
+    fs: &'a dyn FileSystem,
+    /// The searched location type.
+    location: LocationType,
+    /// The directories left to check.
+    dirs: vec::IntoIter<PathBuf>,
+    /// The relative name of the searched entries.
+    name: PathBuf,
+    /// The type of the searched entries.
+    option: LocateOption,
+}
+
+impl Iterator for LocateIter<'_> {
+    type Item = io::Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for dir in self.dirs.by_ref() {
+            let path = dir.join(&self.name);
+            match self.option.check(self.fs, &path) {
+                Ok(true) => return Some(Ok(path)),
+                Ok(false) => {}
+                Err(err)
+                    if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) => {}
+                Err(err) => return Some(Err(Error::io(self.location, path, err).into())),
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.dirs.len()))
+    }
+}
+
+impl FusedIterator for LocateIter<'_> {}
+
+impl StandardPaths {
+    /// Lazily search for all files or directories called `name` in the standard locations.
+    ///
+    /// Unlike [`StandardPaths::locate_all`] each directory of
+    /// [`StandardPaths::standard_locations`] is checked only when the iterator advances,
+    /// so taking the first hits does not touch the rest of the directories.
+    ///
+    /// The missing entries are skipped, while the other I/O errors, like a denied access,
+    /// are yielded as separate items and the iteration continues with the next directory.
+    ///
+    /// Returns [`Error`] if the locations cannot be determined.
+    ///
+    /// # Arguments
+    /// * `location` - the location type where to search.
+    /// * `name` - the name of the files or directories to search.
+    /// * `option` - the type of entries to search.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{LocateOption, LocationType, MapEnvironment, MemoryFileSystem, StandardPaths};
+    /// use std::path::PathBuf;
+    ///
+    /// let fs = MemoryFileSystem::new()
+    ///     .with_file("/home/user/.config/app.conf")
+    ///     .with_file("/etc/xdg/app.conf");
+    /// let sp = StandardPaths::builder()
+    ///     .environment(MapEnvironment::new().with_home("/home/user"))
+    ///     .file_system(fs)
+    ///     .build();
+    /// # #[cfg(target_os = "linux")]
+    /// # {
+    /// let first = sp
+    ///     .locate_iter(LocationType::ConfigLocation, "app.conf", LocateOption::LocateFile)
+    ///     .unwrap()
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(first, PathBuf::from("/home/user/.config/app.conf"));
+    ///
+    /// let all = sp
+    ///     .locate_iter(LocationType::ConfigLocation, "app.conf", LocateOption::LocateFile)
+    ///     .unwrap()
+    ///     .collect::<std::io::Result<Vec<_>>>()
+    ///     .unwrap();
+    /// assert_eq!(all.len(), 2);
+    /// # }
+    /// ```
+    pub fn locate_iter<P>(
+        &self,
+        location: LocationType,
+        name: P,
+        option: LocateOption,
+    ) -> Result<LocateIter<'_>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(LocateIter {
+            fs: self.fs.as_ref(),
+            location,
+            dirs: self.standard_locations(location)?.into_iter(),
+            name: name.as_ref().to_path_buf(),
+            option,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapEnvironment, MemoryFileSystem};
+
+    fn paths(fs: MemoryFileSystem) -> StandardPaths {
+        StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home("/home/user"))
+            .file_system(fs)
+            .override_location(LocationType::AppDataLocation, "/data")
+            .search_dir(LocationType::AppDataLocation, "/extra")
+            .build()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!("LocateBoth".parse(), Ok(LocateOption::LocateBoth));
+        assert_eq!(
+            "locatefile|READABLE".parse(),
+            Ok(LocateOption::LocateFile | LocateOption::Readable)
+        );
+        assert_eq!("".parse(), Ok(LocateOption::empty()));
+        assert_eq!(
+            "LocateFile | Bogus"
+                .parse::<LocateOption>()
+                .unwrap_err()
+                .value(),
+            "Bogus"
+        );
+        assert_eq!(
+            (LocateOption::LocateBoth | LocateOption::NoFollow).to_string(),
+            "LocateBoth | NoFollow"
+        );
+        assert_eq!(
+            format!("{:?}", LocateOption::LocateDirectory),
+            "LocateOption(LocateDirectory)"
+        );
+    }
+
+    #[test]
+    fn entry_types() {
+        let sp = paths(
+            MemoryFileSystem::new()
+                .with_file("/data/target")
+                .with_symlink("/data/link", "/data/target")
+                .with_dir("/extra/link"),
+        );
+        let locate = |option| {
+            sp.locate(LocationType::AppDataLocation, "link", option)
+                .unwrap()
+        };
+        assert_eq!(locate(LocateOption::LocateFile), Some("/data/link".into()));
+        assert_eq!(
+            locate(LocateOption::LocateDirectory),
+            Some("/extra/link".into())
+        );
+        assert_eq!(
+            locate(LocateOption::LocateSymlink),
+            Some("/data/link".into())
+        );
+        assert_eq!(
+            locate(LocateOption::LocateFile | LocateOption::NoFollow),
+            None
+        );
+        assert_eq!(locate(LocateOption::empty()), Some("/data/link".into()));
+    }
+
+    #[test]
+    fn access_flags() {
+        let sp = paths(
+            MemoryFileSystem::new()
+                .with_file("/data/tool")
+                .with_executable("/extra/tool"),
+        );
+        assert_eq!(
+            sp.locate_all(
+                LocationType::AppDataLocation,
+                "tool",
+                LocateOption::LocateFile | LocateOption::Executable
+            )
+            .unwrap(),
+            Some(vec!["/extra/tool".into()])
+        );
+    }
+
+    #[test]
+    fn access_flags_follow_links() {
+        let sp = paths(
+            MemoryFileSystem::new()
+                .with_file("/data/private")
+                .with_mode("/data/private", 0o000)
+                .with_symlink("/data/link", "/data/private")
+                .with_file("/extra/public")
+                .with_symlink("/extra/link", "/extra/public")
+                .with_symlink("/extra/dangling", "/extra/missing"),
+        );
+        let locate_all = |name, option| {
+            sp.locate_all(LocationType::AppDataLocation, name, option)
+                .unwrap()
+        };
+        let readable_link = LocateOption::LocateSymlink | LocateOption::Readable;
+        assert_eq!(
+            locate_all("link", readable_link),
+            Some(vec!["/extra/link".into()])
+        );
+        assert_eq!(
+            locate_all("link", LocateOption::NoFollow | LocateOption::Readable),
+            Some(vec!["/extra/link".into()])
+        );
+        assert_eq!(
+            locate_all("dangling", LocateOption::LocateSymlink),
+            Some(vec!["/extra/dangling".into()])
+        );
+        assert_eq!(locate_all("dangling", readable_link), None);
+    }
+
+    #[test]
+    fn iter_is_lazy_and_reports_errors() {
+        let sp = paths(
+            MemoryFileSystem::new()
+                .with_file("/data/a")
+                .with_file("/extra/a"),
+        );
+        let mut iter = sp
+            .locate_iter(LocationType::AppDataLocation, "a", LocateOption::LocateFile)
+            .unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), PathBuf::from("/data/a"));
+        assert_eq!(iter.next().unwrap().unwrap(), PathBuf::from("/extra/a"));
+        assert!(iter.next().is_none());
+
+        // A symbolic link loop is neither a hit nor a missing entry
+        let sp = paths(
+            MemoryFileSystem::new()
+                .with_symlink("/data/a", "/data/b")
+                .with_symlink("/data/b", "/data/a")
+                .with_file("/extra/a"),
+        );
+        let res = sp
+            .locate_iter(LocationType::AppDataLocation, "a", LocateOption::LocateBoth)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(res.len(), 2);
+        assert!(res[0].is_err());
+        assert_eq!(res[1].as_ref().unwrap(), Path::new("/extra/a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn locate_both_matches_any_entry_type() {
+        use std::os::unix::net::UnixListener;
+
+        let tmp = crate::test_util::TempDir::new();
+        let _socket = UnixListener::bind(tmp.join("app.sock")).unwrap();
+        let sp = StandardPaths::builder()
+            .override_location(LocationType::RuntimeLocation, tmp.path())
+            .build();
+        let locate = |option| {
+            sp.locate(LocationType::RuntimeLocation, "app.sock", option)
+                .unwrap()
+        };
+        assert_eq!(locate(LocateOption::LocateBoth), Some(tmp.join("app.sock")));
+        assert_eq!(locate(LocateOption::empty()), Some(tmp.join("app.sock")));
+        assert_eq!(locate(LocateOption::LocateFile), None);
+        assert_eq!(locate(LocateOption::LocateDirectory), None);
+
+        let query = crate::LocateQuery::new()
+            .location(LocationType::RuntimeLocation)
+            .name("app.sock");
+        assert!(sp.query_first(&query).unwrap().is_some());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_accepts_the_enum_form() {
+        let option: LocateOption = serde_json::from_str("\"LocateDirectory\"").unwrap();
+        assert_eq!(option, LocateOption::LocateDirectory);
+        assert_eq!(
+            serde_json::to_string(&LocateOption::LocateFile).unwrap(),
+            "\"LocateFile\""
+        );
+        let option = LocateOption::LocateFile | LocateOption::Writable;
+        let json = serde_json::to_string(&option).unwrap();
+        assert_eq!(serde_json::from_str::<LocateOption>(&json).unwrap(), option);
+        assert!(serde_json::from_str::<LocateOption>("\"Bogus\"").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use std::{error, fmt, str::FromStr};
+
+use crate::LocationType;
+
+/// The Qt-style names and the short kebab-case aliases of the location types
+/// in the declaration order.
+const NAMES: [(LocationType, &str, &str); 24] = [
+    (LocationType::HomeLocation, "HomeLocation", "home"),
+    (LocationType::DesktopLocation, "DesktopLocation", "desktop"),
+    (
+        LocationType::DocumentsLocation,
+        "DocumentsLocation",
+        "documents",
+    ),
+    (
+        LocationType::DownloadLocation,
+        "DownloadLocation",
+        "download",
+    ),
+    (LocationType::MoviesLocation, "MoviesLocation", "movies"),
+    (LocationType::MusicLocation, "MusicLocation", "music"),
+    (
+        LocationType::PicturesLocation,
+        "PicturesLocation",
+        "pictures",
+    ),
+    (
+        LocationType::ApplicationsLocation,
+        "ApplicationsLocation",
+        "applications",
+    ),
+    (LocationType::FontsLocation, "FontsLocation", "fonts"),
+    (LocationType::RuntimeLocation, "RuntimeLocation", "runtime"),
+    (LocationType::TempLocation, "TempLocation", "temp"),
+    (
+        LocationType::GenericDataLocation,
+        "GenericDataLocation",
+        "generic-data",
+    ),
+    (LocationType::AppDataLocation, "AppDataLocation", "app-data"),
+    (
+        LocationType::AppLocalDataLocation,
+        "AppLocalDataLocation",
+        "app-local-data",
+    ),
+    (
+        LocationType::GenericCacheLocation,
+        "GenericCacheLocation",
+        "generic-cache",
+    ),
+    (
+        LocationType::AppCacheLocation,
+        "AppCacheLocation",
+        "app-cache",
+    ),
+    (LocationType::ConfigLocation, "ConfigLocation", "config"),
+    (
+        LocationType::GenericConfigLocation,
+        "GenericConfigLocation",
+        "generic-config",
+    ),
+    (
+        LocationType::AppConfigLocation,
+        "AppConfigLocation",
+        "app-config",
+    ),
+    (
+        LocationType::GenericStateLocation,
+        "GenericStateLocation",
+        "generic-state",
+    ),
+    (
+        LocationType::AppStateLocation,
+        "AppStateLocation",
+        "app-state",
+    ),
+    (
+        LocationType::TemplatesLocation,
+        "TemplatesLocation",
+        "templates",
+    ),
+    (
+        LocationType::PublicShareLocation,
+        "PublicShareLocation",
+        "public-share",
+    ),
+    (
+        LocationType::UserExecutablesLocation,
+        "UserExecutablesLocation",
+        "user-executables",
+    ),
+];
+
+impl LocationType {
+    /// All the location types in the declaration order.
+    pub(crate) const ALL: [LocationType; 24] = [
+        LocationType::HomeLocation,
+        LocationType::DesktopLocation,
+        LocationType::DocumentsLocation,
+        LocationType::DownloadLocation,
+        LocationType::MoviesLocation,
+        LocationType::MusicLocation,
+        LocationType::PicturesLocation,
+        LocationType::ApplicationsLocation,
+        LocationType::FontsLocation,
+        LocationType::RuntimeLocation,
+        LocationType::TempLocation,
+        LocationType::GenericDataLocation,
+        LocationType::AppDataLocation,
+        LocationType::AppLocalDataLocation,
+        LocationType::GenericCacheLocation,
+        LocationType::AppCacheLocation,
+        LocationType::ConfigLocation,
+        LocationType::GenericConfigLocation,
+        LocationType::AppConfigLocation,
+        LocationType::GenericStateLocation,
+        LocationType::AppStateLocation,
+        LocationType::TemplatesLocation,
+        LocationType::PublicShareLocation,
+        LocationType::UserExecutablesLocation,
+    ];
+
+    /// Returns an iterator over all the location types in the declaration order.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::LocationType;
+    ///
+    /// for location in LocationType::all() {
+    ///     println!("{location}");
+    /// }
+    /// ```
+    pub fn all() -> impl Iterator<Item = LocationType> {
+        LocationType::ALL.iter().copied()
+    }
+
+    /// Returns the Qt-style name of the location type, e.g. `AppConfigLocation`.
+    ///
+    /// It's the same value as returned by the [`Display`](std::fmt::Display) implementation.
+    pub fn name(&self) -> &'static str {
+        NAMES[*self as usize].1
+    }
+
+    /// Returns the short kebab-case alias of the location type, e.g. `app-config`.
+    pub fn alias(&self) -> &'static str {
+        NAMES[*self as usize].2
+    }
+
+    /// Returns `true` if the location is application-specific, i.e. its paths
+    /// contain the organization and the application names.
+    pub fn is_app_specific(&self) -> bool {
+        matches!(
+            self,
+            LocationType::AppDataLocation
+                | LocationType::AppLocalDataLocation
+                | LocationType::AppCacheLocation
+                | LocationType::AppConfigLocation
+                | LocationType::AppStateLocation
+        )
+    }
+
+    /// Returns `true` if the location is shared across applications.
+    pub fn is_generic(&self) -> bool {
+        matches!(
+            self,
+            LocationType::GenericDataLocation
+                | LocationType::GenericCacheLocation
+                | LocationType::GenericConfigLocation
+                | LocationType::GenericStateLocation
+        )
+    }
+
+    /// Returns `true` if the location is one of the user directories
+    /// like desktop, documents or downloads.
+    pub fn is_user_dir(&self) -> bool {
+        matches!(
+            self,
+            LocationType::DesktopLocation
+                | LocationType::DocumentsLocation
+                | LocationType::DownloadLocation
+                | LocationType::MoviesLocation
+                | LocationType::MusicLocation
+                | LocationType::PicturesLocation
+                | LocationType::TemplatesLocation
+                | LocationType::PublicShareLocation
+        )
+    }
+}
+
+impl fmt::Display for LocationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// The error returned when parsing a [`LocationType`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLocationTypeError {
+    /// The string which failed to parse.
+    value: String,
+}
+
+impl ParseLocationTypeError {
+    /// Returns the string which failed to parse.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ParseLocationTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bad location type '{}'", self.value)
+    }
+}
+
+impl error::Error for ParseLocationTypeError {}
+
+impl FromStr for LocationType {
+    type Err = ParseLocationTypeError;
+
+    /// Parses a location type from either its Qt-style name (`AppConfigLocation`)
+    /// or its kebab-case alias (`app-config`).
+    ///
+    /// The comparison is ASCII case-insensitive.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::LocationType;
+    ///
+    /// assert_eq!("AppConfigLocation".parse(), Ok(LocationType::AppConfigLocation));
+    /// assert_eq!("app-config".parse(), Ok(LocationType::AppConfigLocation));
+    /// assert!("nowhere".parse::<LocationType>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMES
+            .iter()
+            .find(|(_, name, alias)| name.eq_ignore_ascii_case(s) || alias.eq_ignore_ascii_case(s))
+            .map(|(loc, ..)| *loc)
+            .ok_or_else(|| ParseLocationTypeError {
+                value: s.to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_declaration_order() {
+        for (idx, location) in LocationType::all().enumerate() {
+            assert_eq!(NAMES[idx].0, location);
+            assert_eq!(location as usize, idx);
+            assert_eq!(format!("{location:?}"), location.name());
+        }
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for location in LocationType::all() {
+            assert_eq!(location.name().parse(), Ok(location));
+            assert_eq!(location.alias().parse(), Ok(location));
+            assert_eq!(location.to_string().parse(), Ok(location));
+        }
+        assert_eq!("APP-STATE".parse(), Ok(LocationType::AppStateLocation));
+        assert_eq!(
+            format!("{:>14}", LocationType::TempLocation),
+            "  TempLocation"
+        );
+        let err = "app".parse::<LocationType>().unwrap_err();
+        assert_eq!(err.value(), "app");
+        assert_eq!(err.to_string(), "Bad location type 'app'");
+    }
+
+    #[test]
+    fn classification() {
+        let user_dirs = LocationType::all()
+            .filter(LocationType::is_user_dir)
+            .count();
+        assert_eq!(user_dirs, 8);
+        for location in LocationType::all() {
+            assert!(!(location.is_app_specific() && location.is_generic()));
+            assert!(!(location.is_user_dir() && location.is_app_specific()));
+        }
+        assert!(LocationType::AppStateLocation.is_app_specific());
+        assert!(LocationType::GenericStateLocation.is_generic());
+        assert!(!LocationType::ConfigLocation.is_app_specific());
+        assert!(!LocationType::ConfigLocation.is_generic());
+    }
+
+    #[test]
+    fn ordering() {
+        let mut locations = vec![
+            LocationType::TempLocation,
+            LocationType::HomeLocation,
+            LocationType::AppConfigLocation,
+        ];
+        locations.sort();
+        assert_eq!(
+            locations,
+            [
+                LocationType::HomeLocation,
+                LocationType::TempLocation,
+                LocationType::AppConfigLocation
+            ]
+        );
+    }
+}
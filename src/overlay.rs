@@ -0,0 +1,383 @@
+use std::{
+    collections::{btree_set, BTreeSet},
+    ffi::{OsStr, OsString},
+    fmt,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{Error, FileSystem, LocationType, StandardPaths};
+
+/// A merged view of the directory trees of all the standard locations of a type.
+///
+/// A relative path resolves to its copy in the highest-priority directory,
+/// the copies in the lower-priority directories are shadowed, like the files
+/// in `XDG_DATA_HOME` shadow the ones in `XDG_DATA_DIRS`.
+///
+/// The directories are listed lazily one level at a time with [`Overlay::read_dir`].
+///
+/// Is returned by [`StandardPaths::overlay`].
+///
+/// ### Example
+/// ```
+/// use standard_paths::{LocationType, MapEnvironment, MemoryFileSystem, StandardPaths};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("/home/user/.local/share/app/themes/dark.toml")
+///     .with_file("/usr/share/app/themes/dark.toml")
+///     .with_file("/usr/share/app/themes/light.toml");
+/// let sp = StandardPaths::builder()
+///     .app_name("app")
+///     .environment(
+///         MapEnvironment::new()
+///             .with_home("/home/user")
+///             .with_var("XDG_DATA_DIRS", "/usr/share"),
+///     )
+///     .file_system(fs)
+///     .build();
+/// # #[cfg(target_os = "linux")]
+/// # {
+/// let overlay = sp.overlay(LocationType::AppDataLocation).unwrap();
+///
+/// let dark = overlay.resolve("themes/dark.toml").unwrap();
+/// assert_eq!(dark.path(), Path::new("/home/user/.local/share/app/themes/dark.toml"));
+/// assert_eq!(dark.shadowed(), [Path::new("/usr/share/app/themes/dark.toml")]);
+///
+/// let names = overlay
+///     .read_dir("themes")
+///     .map(|entry| entry.relative().to_path_buf())
+///     .collect::<Vec<_>>();
+/// assert_eq!(names, [Path::new("themes/dark.toml"), Path::new("themes/light.toml")]);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Overlay {
+    /// The merged directories in the priority order.
+    layers: Vec<PathBuf>,
+    /// The filesystem the directories are listed on.
+    fs: Arc<dyn FileSystem>,
+}
+
+impl fmt::Debug for Overlay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Overlay")
+            .field("layers", &self.layers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An entry of an [`Overlay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayEntry {
+    /// The path relative to the overlay root.
+    relative: PathBuf,
+    /// The full paths of all the copies in the priority order.
+    sources: Vec<PathBuf>,
+    /// Whether the highest-priority copy is a directory.
+    is_dir: bool,
+}
+
+impl OverlayEntry {
+    /// Returns the path relative to the overlay root.
+    pub fn relative(&self) -> &Path {
+        &self.relative
+    }
+
+    /// Returns the full path of the highest-priority copy.
+    pub fn path(&self) -> &Path {
+        &self.sources[0]
+    }
+
+    /// Returns the full paths of the lower-priority copies shadowed by
+    /// [`OverlayEntry::path`] in the priority order.
+    pub fn shadowed(&self) -> &[PathBuf] {
+        &self.sources[1..]
+    }
+
+    /// Returns the full paths of all the copies in the priority order.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Returns `true` if the highest-priority copy is a directory.
+    ///
+    /// Its merged content could be listed with [`Overlay::read_dir`].
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+}
+
+/// Checks that the `relative` path does not leave the overlay root.
+fn is_inner(relative: &Path) -> bool {
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Collects the copies of the `name` entry of the `dirs` directories.
+///
+/// The copies of another kind than the highest-priority one are skipped,
+/// a file does not shadow a directory and vice versa.
+fn merge_entry(
+    fs: &dyn FileSystem,
+    dirs: &[PathBuf],
+    relative: PathBuf,
+    name: &OsStr,
+) -> Option<OverlayEntry> {
+    let mut sources = Vec::new();
+    let mut is_dir = None;
+    for dir in dirs {
+        let path = dir.join(name);
+        let md = match fs.metadata(&path) {
+            Ok(md) => md,
+            _ => continue,
+        };
+        match is_dir {
+            None => is_dir = Some(md.is_dir()),
+            Some(is_dir) if is_dir != md.is_dir() => continue,
+            _ => {}
+        }
+        sources.push(path);
+    }
+    Some(OverlayEntry {
+        relative,
+        sources,
+        is_dir: is_dir?,
+    })
+}
+
+impl Overlay {
+    /// Returns the merged directories in the priority order.
+    pub fn layers(&self) -> &[PathBuf] {
+        &self.layers
+    }
+
+    /// Returns the copies of the `relative` directory in the priority order.
+    ///
+    /// Returns [`None`] if the path is shadowed by a file on any level.
+    fn dir_layers(&self, relative: &Path) -> Option<Vec<PathBuf>> {
+        let mut dirs: Vec<PathBuf> = self
+            .layers
+            .iter()
+            .filter(|layer| self.fs.is_dir(layer))
+            .cloned()
+            .collect();
+        for component in relative.components() {
+            let name = match component {
+                Component::Normal(name) => name,
+                _ => continue,
+            };
+            let entry = merge_entry(self.fs.as_ref(), &dirs, PathBuf::new(), name)?;
+            if !entry.is_dir {
+                return None;
+            }
+            dirs = entry.sources;
+        }
+        Some(dirs)
+    }
+
+    /// Resolves the `relative` path to all its copies.
+    ///
+    /// A copy is shadowed only by an entry of the same kind,
+    /// the path is not resolved if any of its parents is shadowed by a file.
+    ///
+    /// Returns [`None`] if there is no such entry in any of the directories
+    /// or the path is not a relative path inside the overlay.
+    pub fn resolve<P>(&self, relative: P) -> Option<OverlayEntry>
+    where
+        P: AsRef<Path>,
+    {
+        let relative = relative.as_ref();
+        if !is_inner(relative) {
+            return None;
+        }
+        match relative.file_name() {
+            Some(name) => {
+                let dirs = self.dir_layers(relative.parent().unwrap_or(relative))?;
+                merge_entry(self.fs.as_ref(), &dirs, relative.to_path_buf(), name)
+            }
+            _ => {
+                let sources = self.dir_layers(relative)?;
+                if sources.is_empty() {
+                    return None;
+                }
+                Some(OverlayEntry {
+                    relative: relative.to_path_buf(),
+                    sources,
+                    is_dir: true,
+                })
+            }
+        }
+    }
+
+    /// Lists the merged content of the `relative` directory sorted by name.
+    ///
+    /// Only the directories with the `relative` path contribute to the listing,
+    /// the files of the same name are skipped. Pass an empty path to list the root.
+    /// The directories which cannot be listed are treated as empty.
+    ///
+    /// The directories are listed on the first call of [`Iterator::next`]
+    /// and the entries are resolved one at a time as in [`Overlay::resolve`].
+    pub fn read_dir<P>(&self, relative: P) -> OverlayReadDir<'_>
+    where
+        P: AsRef<Path>,
+    {
+        OverlayReadDir {
+            overlay: self,
+            relative: relative.as_ref().to_path_buf(),
+            state: None,
+        }
+    }
+}
+
+/// A lazy iterator over the merged content of an [`Overlay`] directory.
+///
+/// Is returned by [`Overlay::read_dir`].
+#[derive(Debug)]
+pub struct OverlayReadDir<'a> {
+    /// The overlay being listed.
+    overlay: &'a Overlay,
+    /// The listed path relative to the overlay root.
+    relative: PathBuf,
+    /// The copies of the listed directory and the remaining names,
+    /// are filled on the first call of [`Iterator::next`].
+    state: Option<(Vec<PathBuf>, btree_set::IntoIter<OsString>)>,
+}
+
+impl Iterator for OverlayReadDir<'_> {
+    type Item = OverlayEntry;
+
+    fn next(&mut self) -> Option<OverlayEntry> {
+        let overlay = self.overlay;
+        let relative = &self.relative;
+        let (dirs, names) = self.state.get_or_insert_with(|| {
+            let dirs = if is_inner(relative) {
+                overlay.dir_layers(relative).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            let names = dirs
+                .iter()
+                .flat_map(|dir| overlay.fs.read_dir(dir).unwrap_or_default())
+                .collect::<BTreeSet<_>>();
+            (dirs, names.into_iter())
+        });
+        names.find_map(|name| merge_entry(overlay.fs.as_ref(), dirs, relative.join(&name), &name))
+    }
+}
+
+impl StandardPaths {
+    /// Returns the merged view of the directory trees of the `location`.
+    ///
+    /// The directories of the [`StandardPaths::standard_locations`] result
+    /// are merged in its priority order. See [`Overlay`] for details.
+    ///
+    /// Returns [`Error`] if the locations cannot be determined.
+    ///
+    /// # Arguments
+    /// * `location` - location type.
+    pub fn overlay(&self, location: LocationType) -> Result<Overlay, Error> {
+        Ok(Overlay {
+            layers: self.standard_locations(location)?,
+            fs: Arc::clone(&self.fs),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    fn overlay(fs: MemoryFileSystem) -> Overlay {
+        Overlay {
+            layers: vec!["/a".into(), "/b".into(), "/c".into()],
+            fs: Arc::new(fs),
+        }
+    }
+
+    fn names(read_dir: OverlayReadDir<'_>) -> Vec<PathBuf> {
+        read_dir.map(|entry| entry.relative).collect()
+    }
+
+    #[test]
+    fn shadowing() {
+        let overlay = overlay(
+            MemoryFileSystem::new()
+                .with_file("/a/x")
+                .with_file("/c/x")
+                .with_file("/b/y"),
+        );
+        let x = overlay.resolve("x").unwrap();
+        assert_eq!(x.path(), Path::new("/a/x"));
+        assert_eq!(x.shadowed(), [PathBuf::from("/c/x")]);
+        assert!(!x.is_dir());
+        assert_eq!(
+            overlay.resolve("y").unwrap().sources(),
+            [PathBuf::from("/b/y")]
+        );
+        assert_eq!(overlay.resolve("z"), None);
+        assert_eq!(overlay.resolve("../a/x"), None);
+        assert_eq!(overlay.resolve("/a/x"), None);
+
+        let root = overlay.resolve("").unwrap();
+        assert!(root.is_dir());
+        assert_eq!(root.sources(), overlay.layers());
+    }
+
+    #[test]
+    fn kinds_do_not_shadow_each_other() {
+        let overlay = overlay(
+            MemoryFileSystem::new()
+                .with_dir("/a/x")
+                .with_file("/b/x")
+                .with_file("/c/x/nested"),
+        );
+        let x = overlay.resolve("x").unwrap();
+        assert!(x.is_dir());
+        assert_eq!(x.sources(), [PathBuf::from("/a/x"), PathBuf::from("/c/x")]);
+        assert_eq!(
+            overlay.resolve("x/nested").unwrap().path(),
+            Path::new("/c/x/nested")
+        );
+
+        let entries = overlay.read_dir("").collect::<Vec<_>>();
+        assert_eq!(entries, [x]);
+    }
+
+    #[test]
+    fn file_shadows_directory_content() {
+        let overlay = overlay(
+            MemoryFileSystem::new()
+                .with_file("/a/x")
+                .with_file("/b/x/nested"),
+        );
+        assert!(!overlay.resolve("x").unwrap().is_dir());
+        assert_eq!(overlay.resolve("x/nested"), None);
+        assert_eq!(names(overlay.read_dir("x")), Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn read_dir_merges_sorted() {
+        let overlay = overlay(
+            MemoryFileSystem::new()
+                .with_file("/a/d/2")
+                .with_file("/b/d/1")
+                .with_file("/b/d/2")
+                .with_dir("/c/d/3"),
+        );
+        assert_eq!(
+            names(overlay.read_dir("d")),
+            [
+                PathBuf::from("d/1"),
+                PathBuf::from("d/2"),
+                PathBuf::from("d/3")
+            ]
+        );
+        let entry = overlay.read_dir("d").nth(1).unwrap();
+        assert_eq!(entry.shadowed(), [PathBuf::from("/b/d/2")]);
+        assert_eq!(names(overlay.read_dir("missing")), Vec::<PathBuf>::new());
+        assert_eq!(names(overlay.read_dir("..")), Vec::<PathBuf>::new());
+    }
+}
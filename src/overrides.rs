@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+
+use crate::{LocationType, StandardPaths};
+
+/// Returns the part of the override variable name which identifies the `location`.
+///
+/// For example `CONFIG` for `MYAPP_CONFIG_DIR`.
+fn env_var_suffix(location: LocationType) -> &'static str {
+    match location {
+        LocationType::HomeLocation => "HOME",
+        LocationType::DesktopLocation => "DESKTOP",
+        LocationType::DocumentsLocation => "DOCUMENTS",
+        LocationType::DownloadLocation => "DOWNLOAD",
+        LocationType::MoviesLocation => "MOVIES",
+        LocationType::MusicLocation => "MUSIC",
+        LocationType::PicturesLocation => "PICTURES",
+        LocationType::ApplicationsLocation => "APPLICATIONS",
+        LocationType::FontsLocation => "FONTS",
+        LocationType::RuntimeLocation => "RUNTIME",
+        LocationType::TempLocation => "TEMP",
+        LocationType::GenericDataLocation => "GENERIC_DATA",
+        LocationType::AppDataLocation => "DATA",
+        LocationType::AppLocalDataLocation => "LOCAL_DATA",
+        LocationType::GenericCacheLocation => "GENERIC_CACHE",
+        LocationType::AppCacheLocation => "CACHE",
+        LocationType::ConfigLocation => "CONFIG_HOME",
+        LocationType::GenericConfigLocation => "GENERIC_CONFIG",
+        LocationType::AppConfigLocation => "CONFIG",
+        LocationType::GenericStateLocation => "GENERIC_STATE",
+        LocationType::AppStateLocation => "STATE",
+        LocationType::TemplatesLocation => "TEMPLATES",
+        LocationType::PublicShareLocation => "PUBLIC_SHARE",
+        LocationType::UserExecutablesLocation => "BIN",
+    }
+}
+
+/// Builds the default override variables prefix from the application name.
+///
+/// For example `my-app` -> `MY_APP`.
+pub(crate) fn env_prefix_from_app(app: &str) -> String {
+    app.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl StandardPaths {
+    /// Returns the name of the environment variable which overrides the `location`
+    /// or [`None`] if the environment overrides are disabled.
+    ///
+    /// The name consists of the prefix set with
+    /// [`StandardPathsBuilder::env_overrides`](crate::StandardPathsBuilder::env_overrides)
+    /// and the location suffix, e.g. `MYAPP_CONFIG_DIR` for
+    /// [`LocationType::AppConfigLocation`] or `MYAPP_CACHE_DIR` for
+    /// [`LocationType::AppCacheLocation`].
+    pub fn override_env_var(&self, location: LocationType) -> Option<String> {
+        self.env_prefix
+            .as_ref()
+            .map(|prefix| format!("{prefix}_{}_DIR", env_var_suffix(location)))
+    }
+
+    /// Returns the path the `location` is overridden with
+    /// or [`None`] if the location is not overridden.
+    ///
+    /// An absolute path from the override environment variable takes precedence
+    /// over the path set with
+    /// [`StandardPathsBuilder::override_location`](crate::StandardPathsBuilder::override_location).
+    /// Both kinds of overrides are ignored in the test mode, so they cannot
+    /// redirect the locations out of the sandbox.
+    ///
+    /// On Linux the override of a generic location also moves the locations
+    /// built on it, e.g. the [`LocationType::GenericDataLocation`] override moves
+    /// [`LocationType::AppDataLocation`], [`LocationType::FontsLocation`] and
+    /// [`LocationType::ApplicationsLocation`] unless they are overridden themselves.
+    pub fn location_override(&self, location: LocationType) -> Option<PathBuf> {
+        if self.is_test_mode_enabled() {
+            return None;
+        }
+        if let Some(name) = self.override_env_var(location) {
+            if let Some(value) = self.environment().var_os(&name) {
+                let path = PathBuf::from(value);
+                if path.is_absolute() {
+                    return Some(path);
+                }
+            }
+        }
+        self.overrides
+            .iter()
+            .find(|(loc, _)| *loc == location)
+            .map(|(_, path)| path.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{LocateOption, MapEnvironment, MemoryFileSystem, StandardPathsBuilder};
+
+    fn builder() -> StandardPathsBuilder {
+        StandardPaths::builder()
+            .app_name("my-app")
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("MY_APP_CACHE_DIR", "/srv/cache")
+                    .with_var("MY_APP_DATA_DIR", "relative"),
+            )
+            .env_overrides()
+    }
+
+    #[test]
+    fn env_var_names() {
+        let sp = builder().build();
+        assert_eq!(
+            sp.override_env_var(LocationType::AppCacheLocation)
+                .as_deref(),
+            Some("MY_APP_CACHE_DIR")
+        );
+        let sp = builder().env_overrides_prefix("X").build();
+        assert_eq!(
+            sp.override_env_var(LocationType::UserExecutablesLocation)
+                .as_deref(),
+            Some("X_BIN_DIR")
+        );
+        assert_eq!(
+            sp.override_env_var(LocationType::ConfigLocation).as_deref(),
+            Some("X_CONFIG_HOME_DIR")
+        );
+        let sp = StandardPaths::builder().app_name("app").build();
+        assert_eq!(sp.override_env_var(LocationType::AppCacheLocation), None);
+    }
+
+    #[test]
+    fn precedence() {
+        let sp = builder()
+            .override_location(LocationType::AppCacheLocation, "/var/cache")
+            .override_location(LocationType::AppDataLocation, "/var/data")
+            .build();
+        assert_eq!(
+            sp.location_override(LocationType::AppCacheLocation),
+            Some(PathBuf::from("/srv/cache"))
+        );
+        // The relative paths from the variables are ignored
+        assert_eq!(
+            sp.location_override(LocationType::AppDataLocation),
+            Some(PathBuf::from("/var/data"))
+        );
+        assert_eq!(sp.location_override(LocationType::AppConfigLocation), None);
+    }
+
+    #[test]
+    fn overrides_are_ignored_in_test_mode() {
+        let sp = builder()
+            .override_location(LocationType::AppDataLocation, "/var/data")
+            .test_root("/sandbox")
+            .file_system(MemoryFileSystem::new())
+            .build();
+        assert_eq!(sp.location_override(LocationType::AppCacheLocation), None);
+        assert_eq!(sp.location_override(LocationType::AppDataLocation), None);
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            sp.writable_location(LocationType::AppDataLocation).unwrap(),
+            Path::new("/sandbox/share/my-app")
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn standard_locations_and_locate() {
+        let sp = builder()
+            .override_location(LocationType::AppConfigLocation, "/etc/my-app")
+            .file_system(
+                MemoryFileSystem::new()
+                    .with_file("/etc/my-app/settings.toml")
+                    .with_file("/home/user/.config/my-app/settings.toml"),
+            )
+            .build();
+        assert_eq!(
+            sp.standard_locations(LocationType::AppConfigLocation)
+                .unwrap()[0],
+            Path::new("/etc/my-app")
+        );
+        assert_eq!(
+            sp.locate(
+                LocationType::AppConfigLocation,
+                "settings.toml",
+                LocateOption::LocateFile
+            )
+            .unwrap(),
+            Some(PathBuf::from("/etc/my-app/settings.toml"))
+        );
+        assert_eq!(
+            sp.locate_all(
+                LocationType::AppConfigLocation,
+                "settings.toml",
+                LocateOption::LocateFile
+            )
+            .unwrap(),
+            Some(vec![PathBuf::from("/etc/my-app/settings.toml")])
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn derived_locations_follow_generic_override() {
+        let sp = builder()
+            .override_location(LocationType::GenericDataLocation, "/srv/share")
+            .override_location(LocationType::AppLocalDataLocation, "/srv/local")
+            .build();
+        let writable = |location| sp.writable_location(location).unwrap();
+        assert_eq!(
+            writable(LocationType::AppDataLocation),
+            Path::new("/srv/share/my-app")
+        );
+        assert_eq!(
+            writable(LocationType::AppLocalDataLocation),
+            Path::new("/srv/local")
+        );
+        assert_eq!(
+            writable(LocationType::FontsLocation),
+            Path::new("/srv/share/fonts")
+        );
+        assert_eq!(
+            sp.standard_locations(LocationType::ApplicationsLocation)
+                .unwrap()[0],
+            Path::new("/srv/share/applications")
+        );
+        // The cache override variable applies to the app cache only
+        assert_eq!(
+            writable(LocationType::GenericCacheLocation),
+            Path::new("/home/user/.cache")
+        );
+    }
+}
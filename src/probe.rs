@@ -0,0 +1,378 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{Error, LocationType, StandardPaths};
+
+/// A directory probed by [`StandardPaths::probe_writable_location`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Candidate {
+    /// The writable directory of the location type.
+    Location(LocationType),
+    /// The writable directory of the location type with the application suffix
+    /// appended, e.g. `/tmp/org/app` for [`LocationType::TempLocation`].
+    AppLocation(LocationType),
+    /// An arbitrary directory.
+    Path(PathBuf),
+}
+
+/// A candidate rejected by [`StandardPaths::probe_writable_location`].
+#[derive(Debug)]
+pub struct Rejection {
+    /// The rejected candidate.
+    candidate: Candidate,
+    /// The reason of the rejection.
+    error: Error,
+}
+
+impl Rejection {
+    /// Returns the rejected candidate.
+    pub fn candidate(&self) -> &Candidate {
+        &self.candidate
+    }
+
+    /// Returns the reason of the rejection.
+    ///
+    /// It's the error of the location resolution or the probe write.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Returns the candidate path if it was resolved.
+    pub fn path(&self) -> Option<&Path> {
+        self.error.path()
+    }
+}
+
+/// The result of [`StandardPaths::probe_writable_location`].
+#[derive(Debug)]
+pub struct ProbedLocation {
+    /// The chosen candidate.
+    candidate: Candidate,
+    /// The chosen directory.
+    path: PathBuf,
+    /// The candidates rejected before the chosen one.
+    rejected: Vec<Rejection>,
+}
+
+impl ProbedLocation {
+    /// Returns the chosen candidate.
+    pub fn candidate(&self) -> &Candidate {
+        &self.candidate
+    }
+
+    /// Returns the chosen directory.
+    ///
+    /// Note: the directory may not exist, but it can be created.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the candidates rejected before the chosen one in the probing order.
+    pub fn rejected(&self) -> &[Rejection] {
+        &self.rejected
+    }
+
+    /// Returns `true` if the first candidate was chosen.
+    pub fn is_primary(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    /// Converts into the chosen directory.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+/// The counter of the probe file names.
+static PROBE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum number of the probe file names tried in a directory.
+const MAX_PROBE_ATTEMPTS: usize = 100;
+
+/// Checks that a file can be created in the `path` directory
+/// or, if it does not exist, in its nearest existing ancestor.
+///
+/// The probe file is removed right after the creation. Its name is unique
+/// within the process. If the name is taken, e.g. by a leftover of another
+/// process, the next one is tried, up to [`MAX_PROBE_ATTEMPTS`] names.
+fn probe_dir(path: &Path) -> io::Result<()> {
+    let mut dir = path;
+    loop {
+        match fs::metadata(dir) {
+            Ok(md) if md.is_dir() => break,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "The path is not a directory",
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                dir = match dir.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => parent,
+                    _ => return Err(err),
+                };
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    let mut attempts = 0;
+    loop {
+        let probe = dir.join(format!(
+            ".standard_paths_probe.{}.{}",
+            process::id(),
+            PROBE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&probe)
+        {
+            Ok(_) => return fs::remove_file(&probe),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                attempts += 1;
+                if attempts == MAX_PROBE_ATTEMPTS {
+                    return Err(err);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+impl StandardPaths {
+    /// Resolves the directory of the `candidate` probed for the `location`.
+    fn candidate_path(
+        &self,
+        location: LocationType,
+        candidate: &Candidate,
+    ) -> Result<PathBuf, Error> {
+        match candidate {
+            Candidate::Location(loc) => self.writable_location(*loc),
+            Candidate::AppLocation(loc) => {
+                let mut path = self.writable_location(*loc)?;
+                self.append_organization_and_app(&mut path);
+                Ok(path)
+            }
+            Candidate::Path(path) if path.is_absolute() => Ok(path.clone()),
+            Candidate::Path(path) => Err(Error::io(
+                location,
+                path,
+                io::Error::new(io::ErrorKind::InvalidInput, "The path must be absolute"),
+            )),
+        }
+    }
+
+    /// Returns the first really writable directory among the writable `location`
+    /// and the `fallbacks`.
+    ///
+    /// Unlike [`StandardPaths::writable_location`] the candidates are checked
+    /// with a probe write instead of the access mode bits, so the read-only mounts
+    /// and the kiosk setups are detected. A missing directory is accepted
+    /// if its nearest existing ancestor is writable.
+    ///
+    /// Note: the probe is written to the real filesystem with [`std::fs`],
+    /// the [`FileSystem`](crate::FileSystem) set with
+    /// [`StandardPathsBuilder::file_system`](crate::StandardPathsBuilder::file_system)
+    /// is not used.
+    ///
+    /// Returns [`Error::NoWritableLocation`] with the reasons of the rejections
+    /// if none of the candidates is writable.
+    ///
+    /// # Arguments
+    /// * `location` - location type.
+    /// * `fallbacks` - the candidates probed in order if the `location` is not writable.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{Candidate, LocationType, StandardPaths};
+    ///
+    /// let sp = StandardPaths::new("app", "org");
+    /// let fallbacks = [
+    ///     Candidate::AppLocation(LocationType::TempLocation),
+    ///     Candidate::Location(LocationType::RuntimeLocation),
+    /// ];
+    /// match sp.probe_writable_location(LocationType::AppConfigLocation, &fallbacks) {
+    ///     Ok(probed) => {
+    ///         for rejection in probed.rejected() {
+    ///             eprintln!("{:?} rejected: {}", rejection.candidate(), rejection.error());
+    ///         }
+    ///         println!("{:?}", probed.path());
+    ///     }
+    ///     Err(err) => eprintln!("{err}"),
+    /// }
+    /// ```
+    pub fn probe_writable_location(
+        &self,
+        location: LocationType,
+        fallbacks: &[Candidate],
+    ) -> Result<ProbedLocation, Error> {
+        let mut rejected = Vec::new();
+        let candidates = Some(Candidate::Location(location))
+            .into_iter()
+            .chain(fallbacks.iter().cloned());
+        for candidate in candidates {
+            let res =
+                self.candidate_path(location, &candidate)
+                    .and_then(|path| match probe_dir(&path) {
+                        Ok(()) => Ok(path),
+                        Err(err) => {
+                            let loc = match candidate {
+                                Candidate::Location(loc) | Candidate::AppLocation(loc) => loc,
+                                Candidate::Path(_) => location,
+                            };
+                            Err(Error::io(loc, path, err))
+                        }
+                    });
+            match res {
+                Ok(path) => {
+                    return Ok(ProbedLocation {
+                        candidate,
+                        path,
+                        rejected,
+                    })
+                }
+                Err(error) => rejected.push(Rejection { candidate, error }),
+            }
+        }
+        Err(Error::NoWritableLocation { location, rejected })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn missing_directory_is_probed_in_ancestor() {
+        let temp = TempDir::new();
+        assert!(probe_dir(&temp.join("a/b/c")).is_ok());
+        assert!(!temp.join("a").exists());
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn taken_probe_names_are_skipped() {
+        let temp = TempDir::new();
+        // The taken names do not prove that the directory is writable
+        let next = PROBE_COUNTER.load(Ordering::Relaxed);
+        for n in next..next + MAX_PROBE_ATTEMPTS / 2 {
+            fs::create_dir(temp.join(format!(".standard_paths_probe.{}.{n}", process::id())))
+                .unwrap();
+        }
+        assert!(probe_dir(temp.path()).is_ok());
+        assert_eq!(
+            fs::read_dir(temp.path()).unwrap().count(),
+            MAX_PROBE_ATTEMPTS / 2
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn taken_probe_name_in_read_only_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // The permissions are not checked for root
+        if nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let temp = TempDir::new();
+        let next = PROBE_COUNTER.load(Ordering::Relaxed);
+        fs::create_dir(temp.join(format!(".standard_paths_probe.{}.{next}", process::id())))
+            .unwrap();
+        fs::set_permissions(temp.path(), fs::Permissions::from_mode(0o555)).unwrap();
+        let res = probe_dir(temp.path());
+        fs::set_permissions(temp.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn concurrent_probes() {
+        let temp = TempDir::new();
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..32 {
+                        probe_dir(temp.path()).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn fallback_chain() {
+        let temp = TempDir::new();
+        fs::write(temp.join("file"), "").unwrap();
+
+        let sp = StandardPaths::builder()
+            .override_location(LocationType::AppConfigLocation, temp.join("file/app"))
+            .build();
+        let fallbacks = [
+            Candidate::Path("relative".into()),
+            Candidate::Path(temp.join("file")),
+            Candidate::Path(temp.join("writable")),
+        ];
+        let probed = sp
+            .probe_writable_location(LocationType::AppConfigLocation, &fallbacks)
+            .unwrap();
+        assert_eq!(probed.candidate(), &fallbacks[2]);
+        assert_eq!(probed.path(), temp.join("writable"));
+        assert!(!probed.is_primary());
+
+        let rejected = probed.rejected();
+        assert_eq!(
+            rejected[0].candidate(),
+            &Candidate::Location(LocationType::AppConfigLocation)
+        );
+        assert_eq!(rejected[0].path(), Some(temp.join("file/app").as_path()));
+        assert_eq!(rejected[1].candidate(), &fallbacks[0]);
+        assert_eq!(rejected[2].candidate(), &fallbacks[1]);
+        assert_eq!(rejected.len(), 3);
+
+        match sp.probe_writable_location(LocationType::AppConfigLocation, &fallbacks[..2]) {
+            Err(Error::NoWritableLocation { location, rejected }) => {
+                assert_eq!(location, LocationType::AppConfigLocation);
+                assert_eq!(rejected.len(), 3);
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn read_only_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // The permissions are not checked for root
+        if nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let temp = TempDir::new();
+        let read_only = temp.join("read-only");
+        fs::create_dir(&read_only).unwrap();
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let sp = StandardPaths::builder()
+            .override_location(LocationType::AppConfigLocation, read_only.join("app"))
+            .build();
+        let fallbacks = [Candidate::Path(temp.join("writable"))];
+        let probed = sp
+            .probe_writable_location(LocationType::AppConfigLocation, &fallbacks)
+            .unwrap();
+        assert_eq!(probed.candidate(), &fallbacks[0]);
+        assert_eq!(
+            probed.rejected()[0].path(),
+            Some(read_only.join("app").as_path())
+        );
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
@@ -0,0 +1,481 @@
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, LocateOption, LocationType, StandardPaths};
+
+/// Enumerates the orders the candidates of a [`LocateQuery`] are checked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryOrder {
+    /// Check every name in a directory before moving to the next directory,
+    /// so a higher-priority location wins over a higher-priority name.
+    ///
+    /// The order is location type, then its directory, then name.
+    /// This is the default value.
+    #[default]
+    LocationMajor,
+    /// Check a name in every directory before moving to the next name,
+    /// so a higher-priority name wins over a higher-priority location.
+    ///
+    /// The order is name, then location type, then its directory.
+    NameMajor,
+}
+
+/// A search for several candidate names across several location types.
+///
+/// Is used to call
+/// [`StandardPaths::query_first`] and
+/// [`StandardPaths::query_all`].
+///
+/// ### Example
+/// ```
+/// use standard_paths::{LocateOption, LocateQuery, LocationType, QueryOrder};
+///
+/// let query = LocateQuery::new()
+///     .locations([
+///         LocationType::AppConfigLocation,
+///         LocationType::ConfigLocation,
+///         LocationType::AppDataLocation,
+///     ])
+///     .names(["settings.toml", "settings.json", "settingsrc"])
+///     .option(LocateOption::LocateFile)
+///     .order(QueryOrder::NameMajor);
+/// assert_eq!(query.get_locations().len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocateQuery {
+    /// Location types in the priority order.
+    locations: Vec<LocationType>,
+    /// Candidate names in the priority order.
+    names: Vec<PathBuf>,
+    /// The type of entries to search.
+    option: LocateOption,
+    /// The order the candidates are checked in.
+    order: QueryOrder,
+}
+
+impl Default for LocateQuery {
+    fn default() -> Self {
+        LocateQuery {
+            locations: Default::default(),
+            names: Default::default(),
+            option: LocateOption::LocateBoth,
+            order: Default::default(),
+        }
+    }
+}
+
+impl LocateQuery {
+    /// Constructs a new empty [`LocateQuery`] searching for the entries of any type
+    /// in the [`QueryOrder::LocationMajor`] order.
+    pub fn new() -> LocateQuery {
+        Default::default()
+    }
+
+    /// Appends a location type to search in.
+    pub fn location(mut self, location: LocationType) -> LocateQuery {
+        self.locations.push(location);
+        self
+    }
+
+    /// Appends several location types to search in.
+    pub fn locations<I>(mut self, locations: I) -> LocateQuery
+    where
+        I: IntoIterator<Item = LocationType>,
+    {
+        self.locations.extend(locations);
+        self
+    }
+
+    /// Appends a candidate name.
+    pub fn name<P>(mut self, name: P) -> LocateQuery
+    where
+        P: Into<PathBuf>,
+    {
+        self.names.push(name.into());
+        self
+    }
+
+    /// Appends several candidate names.
+    pub fn names<I>(mut self, names: I) -> LocateQuery
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.names.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the type of entries to search.
+    pub fn option(mut self, option: LocateOption) -> LocateQuery {
+        self.option = option;
+        self
+    }
+
+    /// Sets the order the candidates are checked in.
+    pub fn order(mut self, order: QueryOrder) -> LocateQuery {
+        self.order = order;
+        self
+    }
+
+    /// Returns the location types in the priority order.
+    pub fn get_locations(&self) -> &[LocationType] {
+        &self.locations
+    }
+
+    /// Returns the candidate names in the priority order.
+    pub fn get_names(&self) -> &[PathBuf] {
+        &self.names
+    }
+
+    /// Returns the type of entries to search.
+    pub fn get_option(&self) -> LocateOption {
+        self.option
+    }
+
+    /// Returns the order the candidates are checked in.
+    pub fn get_order(&self) -> QueryOrder {
+        self.order
+    }
+}
+
+/// An entry found by [`StandardPaths::query_first`] or [`StandardPaths::query_all`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryHit {
+    /// The location type the entry was found in.
+    location: LocationType,
+    /// The candidate name which matched.
+    name: PathBuf,
+    /// The full path.
+    path: PathBuf,
+}
+
+impl QueryHit {
+    /// Returns the location type the entry was found in.
+    pub fn location(&self) -> LocationType {
+        self.location
+    }
+
+    /// Returns the candidate name which matched.
+    pub fn name(&self) -> &Path {
+        &self.name
+    }
+
+    /// Returns the full path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Converts into the full path.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+impl StandardPaths {
+    /// Checks the candidates of the `query` in its order and returns the hits.
+    ///
+    /// Stops at the first hit if `first` is `true`.
+    /// The paths found for several location types are returned only once.
+    /// The missing entries are skipped, while the other I/O errors are returned.
+    fn run_query(&self, query: &LocateQuery, first: bool) -> Result<Vec<QueryHit>, Error> {
+        let mut dirs = Vec::new();
+        for &location in &query.locations {
+            for dir in self.standard_locations(location)? {
+                dirs.push((location, dir));
+            }
+        }
+
+        let candidates: Vec<(&(LocationType, PathBuf), &PathBuf)> = match query.order {
+            QueryOrder::LocationMajor => dirs
+                .iter()
+                .flat_map(|dir| query.names.iter().map(move |name| (dir, name)))
+                .collect(),
+            QueryOrder::NameMajor => query
+                .names
+                .iter()
+                .flat_map(|name| dirs.iter().map(move |dir| (dir, name)))
+                .collect(),
+        };
+
+        let mut res: Vec<QueryHit> = Vec::new();
+        let mut seen = HashSet::new();
+        for ((location, dir), name) in candidates {
+            let path = dir.join(name);
+            match query.option.check(self.fs.as_ref(), &path) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(err)
+                    if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) =>
+                {
+                    continue
+                }
+                Err(err) => return Err(Error::io(*location, path, err)),
+            }
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            res.push(QueryHit {
+                location: *location,
+                name: name.clone(),
+                path,
+            });
+            if first {
+                break;
+            }
+        }
+        Ok(res)
+    }
+
+    /// Search for the first of the candidate names of the `query`
+    /// in its location types.
+    ///
+    /// The candidates are checked in the [`QueryOrder`] of the query
+    /// and the directories of each location type in the priority order of
+    /// [`StandardPaths::standard_locations`].
+    ///
+    /// The missing candidates are skipped.
+    ///
+    /// Returns [`Error`] if accessing one of the locations or candidates failed,
+    /// e.g. if the access is denied, or [`None`] if none of the candidates can be found.
+    ///
+    /// # Arguments
+    /// * `query` - the location types, the candidate names and the search options.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::{
+    ///     LocateOption, LocateQuery, LocationType, MapEnvironment, MemoryFileSystem, QueryOrder,
+    ///     StandardPaths,
+    /// };
+    /// use std::path::Path;
+    ///
+    /// let fs = MemoryFileSystem::new()
+    ///     .with_file("/home/user/.config/app/settingsrc")
+    ///     .with_file("/home/user/.config/settings.toml");
+    /// let sp = StandardPaths::builder()
+    ///     .app_name("app")
+    ///     .environment(MapEnvironment::new().with_home("/home/user"))
+    ///     .file_system(fs)
+    ///     .build();
+    /// let query = LocateQuery::new()
+    ///     .locations([LocationType::AppConfigLocation, LocationType::ConfigLocation])
+    ///     .names(["settings.toml", "settings.json", "settingsrc"])
+    ///     .option(LocateOption::LocateFile);
+    /// # #[cfg(target_os = "linux")]
+    /// # {
+    /// let hit = sp.query_first(&query).unwrap().unwrap();
+    /// assert_eq!(hit.location(), LocationType::AppConfigLocation);
+    /// assert_eq!(hit.name(), Path::new("settingsrc"));
+    ///
+    /// let hit = sp
+    ///     .query_first(&query.order(QueryOrder::NameMajor))
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(hit.location(), LocationType::ConfigLocation);
+    /// assert_eq!(hit.path(), Path::new("/home/user/.config/settings.toml"));
+    /// # }
+    /// ```
+    pub fn query_first(&self, query: &LocateQuery) -> Result<Option<QueryHit>, Error> {
+        Ok(self.run_query(query, true)?.pop())
+    }
+
+    /// Search for all the candidate names of the `query` in its location types.
+    ///
+    /// Returns the hits in the order described in [`StandardPaths::query_first`].
+    /// A path found for several location types is returned once
+    /// tagged with the first of them.
+    ///
+    /// Returns [`Error`] if accessing one of the locations or candidates failed.
+    ///
+    /// # Arguments
+    /// * `query` - the location types, the candidate names and the search options.
+    pub fn query_all(&self, query: &LocateQuery) -> Result<Vec<QueryHit>, Error> {
+        self.run_query(query, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapEnvironment, MemoryFileSystem};
+
+    #[test]
+    fn builder() {
+        let query = LocateQuery::new()
+            .location(LocationType::AppConfigLocation)
+            .locations([LocationType::ConfigLocation])
+            .name("a")
+            .names(["b", "c"]);
+        assert_eq!(
+            query.get_locations(),
+            [
+                LocationType::AppConfigLocation,
+                LocationType::ConfigLocation
+            ]
+        );
+        assert_eq!(
+            query.get_names(),
+            [PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")]
+        );
+        assert_eq!(query.get_option(), LocateOption::LocateBoth);
+        assert_eq!(query.get_order(), QueryOrder::LocationMajor);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn paths(fs: MemoryFileSystem) -> StandardPaths {
+        StandardPaths::builder()
+            .app_name("app")
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("XDG_CONFIG_DIRS", "/etc/xdg"),
+            )
+            .file_system(fs)
+            .build()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn hits(sp: &StandardPaths, query: &LocateQuery) -> Vec<(LocationType, PathBuf)> {
+        sp.query_all(query)
+            .unwrap()
+            .into_iter()
+            .map(|hit| (hit.location(), hit.into_path()))
+            .collect()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn orders() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/home/user/.config/app/b")
+            .with_file("/etc/xdg/app/a")
+            .with_file("/etc/xdg/app/b")
+            .with_dir("/home/user/.config/a");
+        let sp = paths(fs);
+        let query = LocateQuery::new()
+            .locations([
+                LocationType::AppConfigLocation,
+                LocationType::ConfigLocation,
+            ])
+            .names(["a", "b"]);
+
+        assert_eq!(
+            hits(&sp, &query),
+            [
+                (
+                    LocationType::AppConfigLocation,
+                    "/home/user/.config/app/b".into()
+                ),
+                (LocationType::AppConfigLocation, "/etc/xdg/app/a".into()),
+                (LocationType::AppConfigLocation, "/etc/xdg/app/b".into()),
+                (LocationType::ConfigLocation, "/home/user/.config/a".into()),
+            ]
+        );
+        let query = query.order(QueryOrder::NameMajor);
+        assert_eq!(
+            hits(&sp, &query),
+            [
+                (LocationType::AppConfigLocation, "/etc/xdg/app/a".into()),
+                (LocationType::ConfigLocation, "/home/user/.config/a".into()),
+                (
+                    LocationType::AppConfigLocation,
+                    "/home/user/.config/app/b".into()
+                ),
+                (LocationType::AppConfigLocation, "/etc/xdg/app/b".into()),
+            ]
+        );
+        let hit = sp
+            .query_first(&query.option(LocateOption::LocateFile))
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.name(), Path::new("a"));
+        assert_eq!(hit.path(), Path::new("/etc/xdg/app/a"));
+
+        let query = LocateQuery::new()
+            .location(LocationType::ConfigLocation)
+            .name("missing");
+        assert!(sp.query_first(&query).unwrap().is_none());
+        assert!(sp.query_all(&LocateQuery::new()).unwrap().is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn duplicates_keep_the_first_location() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/home/user/.config/app.conf")
+            .with_file("/etc/xdg/app.conf");
+        let sp = paths(fs);
+        // Both location types share the same directories
+        let query = LocateQuery::new()
+            .locations([
+                LocationType::GenericConfigLocation,
+                LocationType::ConfigLocation,
+                LocationType::GenericConfigLocation,
+            ])
+            .names(["app.conf", "./app.conf", "app.conf"]);
+        for order in [QueryOrder::LocationMajor, QueryOrder::NameMajor] {
+            assert_eq!(
+                hits(&sp, &query.clone().order(order)),
+                [
+                    (
+                        LocationType::GenericConfigLocation,
+                        "/home/user/.config/app.conf".into()
+                    ),
+                    (
+                        LocationType::GenericConfigLocation,
+                        "/etc/xdg/app.conf".into()
+                    ),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn errors_are_propagated() {
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new())
+            .file_system(MemoryFileSystem::new())
+            .build();
+        let query = LocateQuery::new()
+            .locations([LocationType::HomeLocation])
+            .name("a");
+        assert!(matches!(
+            sp.query_all(&query),
+            Err(Error::HomeNotFound { .. })
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn check_errors_are_propagated() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/home/user/.config/app/a/b")
+            .with_file("/home/user/.config/app/loop")
+            .with_symlink("/etc/xdg/app/loop", "/etc/xdg/app/pool")
+            .with_symlink("/etc/xdg/app/pool", "/etc/xdg/app/loop");
+        let sp = paths(fs);
+        // The missing entries and the files in place of directories are skipped
+        let query = LocateQuery::new()
+            .location(LocationType::AppConfigLocation)
+            .names(["missing", "a/b/c"]);
+        assert!(sp.query_all(&query).unwrap().is_empty());
+        // The first hit is found before the link cycle is checked
+        let query = LocateQuery::new()
+            .location(LocationType::AppConfigLocation)
+            .name("loop");
+        assert_eq!(
+            sp.query_first(&query).unwrap().unwrap().path(),
+            Path::new("/home/user/.config/app/loop")
+        );
+        match sp.query_all(&query) {
+            Err(Error::Io { location, path, .. }) => {
+                assert_eq!(location, Some(LocationType::AppConfigLocation));
+                assert_eq!(path.as_deref(), Some(Path::new("/etc/xdg/app/loop")));
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{LocationType, StandardPaths};
+
+/// The resolved paths of a single location type.
+///
+/// Is a part of [`Snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LocationSnapshot {
+    /// The location type.
+    pub location: LocationType,
+    /// The result of [`StandardPaths::writable_location`] if it succeeded.
+    pub writable_location: Option<PathBuf>,
+    /// The result of [`StandardPaths::standard_locations`] if it succeeded.
+    pub standard_locations: Vec<PathBuf>,
+    /// The description of the error if any of the methods failed.
+    pub error: Option<String>,
+}
+
+/// The resolved paths of every [`LocationType`].
+///
+/// Is returned by [`StandardPaths::snapshot`]. With the `serde` feature enabled
+/// it implements `Serialize` and `Deserialize`, so it could be shipped
+/// in diagnostics.
+///
+/// ### Example
+/// ```
+/// use standard_paths::StandardPaths;
+///
+/// let sp = StandardPaths::new("app", "org");
+/// let snapshot = sp.snapshot();
+/// # #[cfg(feature = "serde")]
+/// println!("{}", serde_json::to_string_pretty(&snapshot).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snapshot {
+    /// The application name.
+    pub app_name: String,
+    /// The organization name.
+    pub org_name: String,
+    /// The resolved paths by location type.
+    pub locations: Vec<LocationSnapshot>,
+}
+
+impl Snapshot {
+    /// Returns the resolved paths of the `location`.
+    pub fn get(&self, location: LocationType) -> Option<&LocationSnapshot> {
+        self.locations.iter().find(|s| s.location == location)
+    }
+}
+
+impl StandardPaths {
+    /// Resolves the writable location and the standard locations of every [`LocationType`].
+    ///
+    /// The resolution errors do not abort the snapshot, they are stored
+    /// in the appropriate [`LocationSnapshot::error`] instead.
+    pub fn snapshot(&self) -> Snapshot {
+        let locations = LocationType::ALL
+            .iter()
+            .map(|&location| {
+                let mut error = None;
+                let writable_location = match self.writable_location(location) {
+                    Ok(path) => Some(path),
+                    Err(err) => {
+                        error = Some(err.to_string());
+                        None
+                    }
+                };
+                let standard_locations = match self.standard_locations(location) {
+                    Ok(paths) => paths,
+                    Err(err) => {
+                        error.get_or_insert_with(|| err.to_string());
+                        Vec::new()
+                    }
+                };
+                LocationSnapshot {
+                    location,
+                    writable_location,
+                    standard_locations,
+                    error,
+                }
+            })
+            .collect();
+        Snapshot {
+            app_name: self.app_name.clone(),
+            org_name: self.org_name.clone(),
+            locations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapEnvironment, MemoryFileSystem};
+
+    fn snapshot(env: MapEnvironment) -> Snapshot {
+        StandardPaths::builder()
+            .app_name("app")
+            .org_name("org")
+            .environment(env)
+            .file_system(MemoryFileSystem::new())
+            .build()
+            .snapshot()
+    }
+
+    #[test]
+    fn every_location() {
+        let snapshot = snapshot(MapEnvironment::new().with_home("/home/user"));
+        assert_eq!(snapshot.app_name, "app");
+        assert_eq!(snapshot.org_name, "org");
+        assert_eq!(snapshot.locations.len(), LocationType::ALL.len());
+        let home = snapshot.get(LocationType::HomeLocation).unwrap();
+        assert_eq!(home.writable_location, Some(PathBuf::from("/home/user")));
+        assert_eq!(home.standard_locations, [PathBuf::from("/home/user")]);
+        assert_eq!(home.error, None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn errors_are_stored() {
+        let snapshot = snapshot(MapEnvironment::new());
+        let config = snapshot.get(LocationType::AppConfigLocation).unwrap();
+        assert_eq!(config.writable_location, None);
+        assert!(config.standard_locations.is_empty());
+        assert_eq!(
+            config.error.as_deref(),
+            Some("Error getting HOME directory for AppConfigLocation")
+        );
+        // The other locations are still resolved
+        let temp = snapshot.get(LocationType::TempLocation).unwrap();
+        assert_eq!(temp.writable_location, Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        let snapshot = snapshot(MapEnvironment::new().with_home("/home/user"));
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("\"location\":\"AppConfigLocation\""));
+        assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+    }
+}
@@ -0,0 +1,338 @@
+#[cfg(target_os = "linux")]
+use std::{ffi::OsString, io, path::Path};
+use std::{
+    path::PathBuf,
+    sync::{PoisonError, RwLock},
+};
+
+use crate::StandardPaths;
+#[cfg(target_os = "linux")]
+use crate::{linux::user_dirs_file, user_dirs::USER_DIRS, Environment, LocationType};
+
+/// The sandbox root used by the test mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TestRoot {
+    /// The `~/.qttest` directory as in `QStandardPaths`.
+    Default,
+    /// A caller-supplied directory.
+    Custom(PathBuf),
+}
+
+/// The global test mode setting, [`None`] if the test mode is disabled.
+static GLOBAL_TEST_ROOT: RwLock<Option<TestRoot>> = RwLock::new(None);
+
+/// Name of the default sandbox root inside the home directory.
+const DEFAULT_TEST_DIR: &str = ".qttest";
+
+impl StandardPaths {
+    /// Enables or disables the test mode for all the [`StandardPaths`] instances.
+    ///
+    /// In the test mode every writable location is redirected into the `~/.qttest`
+    /// sandbox, so the tests never touch the real user configuration.
+    /// Without a home directory the sandbox is located in the temporary directory.
+    /// Use [`StandardPaths::set_test_mode_root`] to choose another sandbox root.
+    ///
+    /// The instances configured with [`StandardPathsBuilder::test_root`](crate::StandardPathsBuilder::test_root)
+    /// keep using their own roots.
+    pub fn set_test_mode_enabled(enabled: bool) {
+        let root = if enabled {
+            Some(TestRoot::Default)
+        } else {
+            None
+        };
+        *GLOBAL_TEST_ROOT
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = root;
+    }
+
+    /// Enables the test mode for all the [`StandardPaths`] instances
+    /// with the sandbox located at `root`, e.g. a temporary directory.
+    ///
+    /// See [`StandardPaths::set_test_mode_enabled`] for details.
+    pub fn set_test_mode_root<P>(root: P)
+    where
+        P: Into<PathBuf>,
+    {
+        *GLOBAL_TEST_ROOT
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(TestRoot::Custom(root.into()));
+    }
+
+    /// Returns `true` if the test mode is enabled for this instance
+    /// either explicitly or globally.
+    pub fn is_test_mode_enabled(&self) -> bool {
+        self.test_root.is_some()
+            || GLOBAL_TEST_ROOT
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .is_some()
+    }
+
+    /// Returns the sandbox root the writable locations are redirected into
+    /// or [`None`] if the test mode is disabled.
+    ///
+    /// The sandbox has the following layout:
+    /// * `home` - the home directory and the user directories;
+    /// * `config` - the configuration files;
+    /// * `share` - the persistent data;
+    /// * `cache` - the cached data;
+    /// * `state` - the state data;
+    /// * `bin` - the user executables;
+    /// * `runtime` - the runtime communication files;
+    /// * `tmp` - the temporary files.
+    pub fn test_root(&self) -> Option<PathBuf> {
+        let root = match &self.test_root {
+            Some(root) => root.clone(),
+            _ => GLOBAL_TEST_ROOT
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()?,
+        };
+        match root {
+            TestRoot::Custom(path) => Some(path),
+            TestRoot::Default => {
+                // The test mode must never fall back to the real locations
+                let env = self.environment();
+                let mut path = env.home_dir().unwrap_or_else(|| env.temp_dir());
+                path.push(DEFAULT_TEST_DIR);
+                Some(path)
+            }
+        }
+    }
+}
+
+/// An [`Environment`] which redirects the base directories into the test mode sandbox
+/// if it is enabled and forwards everything to the wrapped environment otherwise.
+#[cfg(target_os = "linux")]
+pub(crate) struct SandboxEnvironment<'a> {
+    /// The wrapped environment.
+    inner: &'a dyn Environment,
+    /// The sandbox root.
+    root: Option<PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl SandboxEnvironment<'_> {
+    /// Returns the sandbox root if the test mode is enabled.
+    pub(crate) fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl StandardPaths {
+    /// Returns the environment the writable locations should be resolved in.
+    ///
+    /// The sandbox is prepared on the first use of every test mode root.
+    pub(crate) fn sandboxed_environment(&self) -> SandboxEnvironment<'_> {
+        let env = SandboxEnvironment {
+            inner: self.environment(),
+            root: self.test_root(),
+        };
+        if env.root().is_some() {
+            self.prepare_sandbox(&env);
+        }
+        env
+    }
+
+    /// Writes the sandbox `user-dirs.dirs` file pointing every user directory
+    /// to its English name inside the sandbox home directory unless the sandbox
+    /// of the `env` has been prepared by the instance already.
+    ///
+    /// The file is not overwritten if it exists. It is written through
+    /// the [`FileSystem`](crate::FileSystem) of the instance, the failures are ignored
+    /// as the user directories resolve to the same paths without the file.
+    fn prepare_sandbox(&self, env: &SandboxEnvironment<'_>) {
+        let mut prepared = self
+            .prepared_test_root
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if prepared.as_deref() == env.root() {
+            return;
+        }
+        *prepared = env.root().map(Path::to_path_buf);
+
+        let path = match user_dirs_file(env, self.strictness(), LocationType::DesktopLocation) {
+            Ok(path) if !self.file_system().exists(&path) => path,
+            _ => return,
+        };
+        let mut content = String::from("# Generated by standard_paths for the test mode\n");
+        for (_, key, name) in &USER_DIRS {
+            content.push_str(&format!("XDG_{key}_DIR=\"$HOME/{name}\"\n"));
+        }
+        if let Some(parent) = path.parent() {
+            self.file_system().create_dir_all(parent).ok();
+        }
+        self.file_system().write(&path, content.as_bytes()).ok();
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Environment for SandboxEnvironment<'_> {
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        let root = match &self.root {
+            Some(root) => root,
+            _ => return self.inner.var_os(name),
+        };
+        let dir = match name {
+            "HOME" => "home",
+            "XDG_CONFIG_HOME" => "config",
+            "XDG_DATA_HOME" => "share",
+            "XDG_CACHE_HOME" => "cache",
+            "XDG_STATE_HOME" => "state",
+            "XDG_BIN_HOME" => "bin",
+            "XDG_RUNTIME_DIR" => "runtime",
+            "TMPDIR" => "tmp",
+            _ => return self.inner.var_os(name),
+        };
+        Some(root.join(dir).into())
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        match &self.root {
+            Some(root) => Some(root.join("home")),
+            _ => self.inner.home_dir(),
+        }
+    }
+
+    fn temp_dir(&self) -> PathBuf {
+        match &self.root {
+            Some(root) => root.join("tmp"),
+            _ => self.inner.temp_dir(),
+        }
+    }
+
+    fn uid(&self) -> Option<u32> {
+        self.inner.uid()
+    }
+
+    fn user_name(&self) -> io::Result<String> {
+        self.inner.user_name()
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{test_util::TempDir, MapEnvironment, MemoryFileSystem, UserDirs};
+
+    #[test]
+    fn sandboxed_environment() {
+        let sp = StandardPaths::builder()
+            .environment(
+                MapEnvironment::new()
+                    .with_home("/home/user")
+                    .with_var("XDG_CONFIG_HOME", "/home/user/.config")
+                    .with_var("LANG", "de_DE.UTF-8")
+                    .with_uid(1000),
+            )
+            .test_root("/sandbox")
+            .file_system(MemoryFileSystem::new())
+            .build();
+        let env = sp.sandboxed_environment();
+        assert_eq!(env.root(), Some(Path::new("/sandbox")));
+        assert_eq!(env.home_dir(), Some(PathBuf::from("/sandbox/home")));
+        assert_eq!(env.temp_dir(), Path::new("/sandbox/tmp"));
+        assert_eq!(
+            env.var_os("XDG_CONFIG_HOME"),
+            Some(OsString::from("/sandbox/config"))
+        );
+        assert_eq!(env.var_os("LANG"), Some(OsString::from("de_DE.UTF-8")));
+        assert_eq!(env.uid(), Some(1000));
+    }
+
+    #[test]
+    fn default_root() {
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home("/home/user"))
+            .test_mode(true)
+            .file_system(MemoryFileSystem::new())
+            .build();
+        assert!(sp.is_test_mode_enabled());
+        assert_eq!(sp.test_root(), Some(PathBuf::from("/home/user/.qttest")));
+    }
+
+    #[test]
+    fn default_root_without_home() {
+        let sp = StandardPaths::builder()
+            .environment(
+                MapEnvironment::new()
+                    .with_temp_dir("/tmp")
+                    .with_var("XDG_CONFIG_HOME", "/home/user/.config"),
+            )
+            .test_mode(true)
+            .file_system(MemoryFileSystem::new())
+            .build();
+        assert_eq!(sp.test_root(), Some(PathBuf::from("/tmp/.qttest")));
+        assert_eq!(
+            sp.writable_location(LocationType::GenericConfigLocation)
+                .unwrap(),
+            Path::new("/tmp/.qttest/config")
+        );
+    }
+
+    #[test]
+    fn user_dirs_are_written_on_first_use() {
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home("/home/user"))
+            .test_root("/sandbox")
+            .file_system(MemoryFileSystem::new())
+            .build();
+        let config = Path::new("/sandbox/config/user-dirs.dirs");
+        assert!(!sp.file_system().exists(config));
+        assert_eq!(
+            sp.writable_location(LocationType::MoviesLocation).unwrap(),
+            Path::new("/sandbox/home/Videos")
+        );
+        let content = sp.file_system().read(config).unwrap();
+        let dirs = UserDirs::parse("user-dirs.dirs", content).unwrap();
+        assert_eq!(dirs.entries().count(), USER_DIRS.len());
+    }
+
+    #[test]
+    fn existing_user_dirs_are_kept() {
+        let fs = MemoryFileSystem::new().with_file_content(
+            "/sandbox/config/user-dirs.dirs",
+            "XDG_DESKTOP_DIR=\"$HOME/Work\"\n",
+        );
+        let sp = StandardPaths::builder()
+            .test_root("/sandbox")
+            .file_system(fs)
+            .build();
+        assert_eq!(
+            sp.writable_location(LocationType::DesktopLocation).unwrap(),
+            Path::new("/sandbox/home/Work")
+        );
+        assert_eq!(
+            sp.writable_location(LocationType::MusicLocation).unwrap(),
+            Path::new("/sandbox/home/Music")
+        );
+    }
+
+    #[test]
+    fn sandbox_is_prepared_once() {
+        let root = TempDir::new();
+        let mut sp = StandardPaths::builder().test_root(root.path()).build();
+        let config = root.join("config/user-dirs.dirs");
+        assert!(!config.exists());
+        assert_eq!(
+            sp.writable_location(LocationType::DesktopLocation).unwrap(),
+            root.join("home/Desktop")
+        );
+        assert!(config.is_file());
+        fs::remove_file(&config).unwrap();
+        assert_eq!(
+            sp.writable_location(LocationType::DesktopLocation).unwrap(),
+            root.join("home/Desktop")
+        );
+        assert!(!config.exists());
+
+        // A new filesystem gets its own sandbox
+        sp.set_file_system(MemoryFileSystem::new());
+        sp.writable_location(LocationType::TempLocation).unwrap();
+        assert!(sp.file_system().is_file(&config));
+        assert!(!config.exists());
+    }
+}
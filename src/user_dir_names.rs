@@ -0,0 +1,319 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::{display_name::locale_tags, user_dirs::USER_DIRS, LocationType};
+
+/// The English names of the user directories in the order of the translations below.
+const ENGLISH: [&str; 8] = [
+    "Desktop",
+    "Documents",
+    "Downloads",
+    "Music",
+    "Pictures",
+    "Public",
+    "Templates",
+    "Videos",
+];
+
+/// The translations of the user directory names used by xdg-user-dirs by locale.
+const TRANSLATIONS: [(&str, [&str; 8]); 14] = [
+    (
+        "de",
+        [
+            "Schreibtisch",
+            "Dokumente",
+            "Downloads",
+            "Musik",
+            "Bilder",
+            "Öffentlich",
+            "Vorlagen",
+            "Videos",
+        ],
+    ),
+    (
+        "es",
+        [
+            "Escritorio",
+            "Documentos",
+            "Descargas",
+            "Música",
+            "Imágenes",
+            "Público",
+            "Plantillas",
+            "Vídeos",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "Bureau",
+            "Documents",
+            "Téléchargements",
+            "Musique",
+            "Images",
+            "Public",
+            "Modèles",
+            "Vidéos",
+        ],
+    ),
+    (
+        "it",
+        [
+            "Scrivania",
+            "Documenti",
+            "Scaricati",
+            "Musica",
+            "Immagini",
+            "Pubblici",
+            "Modelli",
+            "Video",
+        ],
+    ),
+    (
+        "ja",
+        [
+            "デスクトップ",
+            "ドキュメント",
+            "ダウンロード",
+            "音楽",
+            "画像",
+            "公開",
+            "テンプレート",
+            "ビデオ",
+        ],
+    ),
+    (
+        "nl",
+        [
+            "Bureaublad",
+            "Documenten",
+            "Downloads",
+            "Muziek",
+            "Afbeeldingen",
+            "Openbaar",
+            "Sjablonen",
+            "Video's",
+        ],
+    ),
+    (
+        "pl",
+        [
+            "Pulpit",
+            "Dokumenty",
+            "Pobrane",
+            "Muzyka",
+            "Obrazy",
+            "Publiczny",
+            "Szablony",
+            "Wideo",
+        ],
+    ),
+    (
+        "pt",
+        [
+            "Área de Trabalho",
+            "Documentos",
+            "Transferências",
+            "Música",
+            "Imagens",
+            "Público",
+            "Modelos",
+            "Vídeos",
+        ],
+    ),
+    (
+        "pt_BR",
+        [
+            "Área de trabalho",
+            "Documentos",
+            "Downloads",
+            "Música",
+            "Imagens",
+            "Público",
+            "Modelos",
+            "Vídeos",
+        ],
+    ),
+    (
+        "ru",
+        [
+            "Рабочий стол",
+            "Документы",
+            "Загрузки",
+            "Музыка",
+            "Изображения",
+            "Общедоступные",
+            "Шаблоны",
+            "Видео",
+        ],
+    ),
+    (
+        "sv",
+        [
+            "Skrivbord",
+            "Dokument",
+            "Hämtningar",
+            "Musik",
+            "Bilder",
+            "Publikt",
+            "Mallar",
+            "Video",
+        ],
+    ),
+    (
+        "uk",
+        [
+            "Стільниця",
+            "Документи",
+            "Завантаження",
+            "Музика",
+            "Зображення",
+            "Загальнодоступні",
+            "Шаблони",
+            "Відео",
+        ],
+    ),
+    (
+        "zh_CN",
+        [
+            "桌面", "文档", "下载", "音乐", "图片", "公共", "模板", "视频",
+        ],
+    ),
+    (
+        "zh_TW",
+        [
+            "桌面", "文件", "下載", "音樂", "圖片", "公共", "範本", "影片",
+        ],
+    ),
+];
+
+/// Finds the translated names for the POSIX-style `locale`.
+fn find_names(locale: &str) -> Option<&'static [&'static str; 8]> {
+    locale_tags(locale).iter().find_map(|code| {
+        TRANSLATIONS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(code))
+            .map(|(_, names)| names)
+    })
+}
+
+/// Translates the English user directory `name` into the `locale`.
+///
+/// Returns the `name` itself if it's not a standard name or there is no translation.
+pub(crate) fn translate_user_dir_name<'a>(name: &'a str, locale: &str) -> &'a str {
+    let idx = match ENGLISH.iter().position(|english| *english == name) {
+        Some(idx) => idx,
+        _ => return name,
+    };
+    match find_names(locale) {
+        Some(names) => names[idx],
+        _ => name,
+    }
+}
+
+/// Translates every component of the relative `path` which is a standard
+/// user directory name into the `locale`, e.g. `Desktop/Downloads`
+/// into `Schreibtisch/Downloads` for `de`.
+pub(crate) fn translate_user_dir_path(path: &Path, locale: &str) -> PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => match name.to_str() {
+                Some(name) => translate_user_dir_name(name, locale).into(),
+                _ => PathBuf::from(name),
+            },
+            component => PathBuf::from(component.as_os_str()),
+        })
+        .collect()
+}
+
+impl LocationType {
+    /// Returns the default name of the user directory in the `locale`
+    /// as xdg-user-dirs would create it in the home directory,
+    /// or [`None`] if the location is not a user directory.
+    ///
+    /// The `locale` is a POSIX-style locale name like `de` or `pt_BR.UTF-8`.
+    /// Unknown locales fall back to the English names.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::LocationType;
+    ///
+    /// assert_eq!(
+    ///     LocationType::DesktopLocation.user_dir_name("de_DE.UTF-8"),
+    ///     Some("Schreibtisch")
+    /// );
+    /// assert_eq!(LocationType::DownloadLocation.user_dir_name("C"), Some("Downloads"));
+    /// assert_eq!(LocationType::TempLocation.user_dir_name("de"), None);
+    /// ```
+    pub fn user_dir_name(&self, locale: &str) -> Option<&'static str> {
+        let (_, _, name) = USER_DIRS.iter().find(|(loc, ..)| loc == self)?;
+        Some(translate_user_dir_name(name, locale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_names_match_user_dirs() {
+        for (_, _, name) in USER_DIRS {
+            assert!(ENGLISH.contains(&name));
+        }
+        assert!(ENGLISH.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(translate_user_dir_name("Music", "de"), "Musik");
+        assert_eq!(translate_user_dir_name("Music", "xx_YY"), "Music");
+        assert_eq!(translate_user_dir_name("Musik", "de"), "Musik");
+        assert_eq!(translate_user_dir_name("music", "de"), "music");
+        // The full language tag wins over the language alone
+        assert_eq!(
+            translate_user_dir_name("Desktop", "pt_BR.UTF-8"),
+            "Área de trabalho"
+        );
+        assert_eq!(
+            translate_user_dir_name("Desktop", "pt_PT"),
+            "Área de Trabalho"
+        );
+        assert_eq!(translate_user_dir_name("Videos", "zh_tw"), "影片");
+    }
+
+    #[test]
+    fn paths() {
+        let translate = |path: &str| translate_user_dir_path(Path::new(path), "de_DE");
+        assert_eq!(
+            translate("Desktop/Downloads"),
+            Path::new("Schreibtisch/Downloads")
+        );
+        assert_eq!(
+            translate("Desktop/Music/Other"),
+            Path::new("Schreibtisch/Musik/Other")
+        );
+        assert_eq!(translate("/srv/Music"), Path::new("/srv/Musik"));
+        assert_eq!(
+            translate("./Music/../Videos"),
+            Path::new("./Musik/../Videos")
+        );
+        assert_eq!(translate(""), Path::new(""));
+    }
+
+    #[test]
+    fn location_names() {
+        assert_eq!(
+            LocationType::MoviesLocation.user_dir_name("en_US"),
+            Some("Videos")
+        );
+        assert_eq!(
+            LocationType::PublicShareLocation.user_dir_name("de"),
+            Some("Öffentlich")
+        );
+        for location in LocationType::all() {
+            assert_eq!(
+                location.user_dir_name("fr").is_some(),
+                location.is_user_dir()
+            );
+        }
+    }
+}
@@ -0,0 +1,1384 @@
+use std::{
+    borrow::Cow,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(target_os = "linux")]
+use crate::{linux::user_dirs_file, Environment, StandardPaths};
+use crate::{Error, FileSystem, LocationType, StdFileSystem, Strictness};
+
+/// The `user-dirs.dirs` keys and the default directory names of the user directories.
+pub(crate) const USER_DIRS: [(LocationType, &str, &str); 8] = [
+    (LocationType::DesktopLocation, "DESKTOP", "Desktop"),
+    (LocationType::DocumentsLocation, "DOCUMENTS", "Documents"),
+    (LocationType::PicturesLocation, "PICTURES", "Pictures"),
+    (LocationType::MusicLocation, "MUSIC", "Music"),
+    (LocationType::MoviesLocation, "VIDEOS", "Videos"),
+    (LocationType::DownloadLocation, "DOWNLOAD", "Downloads"),
+    (LocationType::TemplatesLocation, "TEMPLATES", "Templates"),
+    (LocationType::PublicShareLocation, "PUBLICSHARE", "Public"),
+];
+
+/// Returns the `user-dirs.dirs` key of the `location`, e.g. `DESKTOP`
+/// for [`LocationType::DesktopLocation`].
+pub(crate) fn user_dir_key(location: LocationType) -> Option<&'static str> {
+    USER_DIRS
+        .iter()
+        .find(|(loc, ..)| *loc == location)
+        .map(|(_, key, _)| *key)
+}
+
+/// A single `XDG_<KEY>_DIR` entry of a `user-dirs.dirs` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDirEntry {
+    /// The key without the `XDG_` prefix and the `_DIR` suffix.
+    key: String,
+    /// The unquoted path, relative to the home directory if `home_relative` is set.
+    path: PathBuf,
+    /// Whether the value starts with `$HOME`.
+    home_relative: bool,
+}
+
+impl UserDirEntry {
+    /// Returns the entry key without the `XDG_` prefix and the `_DIR` suffix, e.g. `DESKTOP`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the unquoted path of the entry.
+    ///
+    /// The path is relative to the home directory if
+    /// [`UserDirEntry::is_home_relative`] returns `true`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `true` if the value starts with `$HOME`.
+    pub fn is_home_relative(&self) -> bool {
+        self.home_relative
+    }
+
+    /// Constructs a new entry for the `path` making it relative to the `home` directory
+    /// if it's located inside of it.
+    ///
+    /// A relative `path` is considered relative to the `home` directory.
+    fn new(key: &str, path: &Path, home: &Path) -> UserDirEntry {
+        let (path, home_relative) = if path.is_relative() {
+            (path.to_path_buf(), true)
+        } else {
+            match path.strip_prefix(home) {
+                Ok(rest) => (rest.to_path_buf(), true),
+                _ => (path.to_path_buf(), false),
+            }
+        };
+        UserDirEntry {
+            key: key.into(),
+            path,
+            home_relative,
+        }
+    }
+
+    /// Formats the entry in the canonical `XDG_<KEY>_DIR="$HOME/<path>"` form.
+    fn to_line(&self) -> Vec<u8> {
+        let mut line = format!("XDG_{}_DIR=\"", self.key).into_bytes();
+        if self.home_relative {
+            line.extend_from_slice(b"$HOME/");
+        }
+        for &b in path_to_bytes(&self.path).iter() {
+            if matches!(b, b'"' | b'\\' | b'$' | b'`') {
+                line.push(b'\\');
+            }
+            line.push(b);
+        }
+        line.push(b'"');
+        line
+    }
+
+    /// Returns the absolute path of the entry expanding `$HOME` to the `home` directory.
+    pub fn resolve<P>(&self, home: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        if self.home_relative {
+            home.as_ref().join(&self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+/// A line of a `user-dirs.dirs` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// A comment, an empty line or an unrelated variable kept verbatim.
+    Other(Vec<u8>),
+    /// A user directory entry and its original text.
+    Entry(UserDirEntry, Vec<u8>),
+}
+
+/// The contents of a `user-dirs.dirs` file written by
+/// [xdg-user-dirs](http://www.freedesktop.org/wiki/Software/xdg-user-dirs).
+///
+/// The file is a shell script assigning the `XDG_<KEY>_DIR` variables.
+/// The values may be quoted with double or single quotes, contain backslash escapes
+/// and start with `$HOME` or `${HOME}`. The comments and the unrelated lines
+/// are preserved as is.
+///
+/// The malformed lines are preserved too and reported by [`UserDirs::issues`]
+/// unless the file is parsed with [`Strictness::Strict`].
+///
+/// ### Example
+/// ```
+/// use standard_paths::{LocationType, Strictness, UserDirs};
+/// use std::path::PathBuf;
+///
+/// let content = r#"
+/// ## This file is written by xdg-user-dirs-update
+/// XDG_DESKTOP_DIR="$HOME/Arbeitsfläche"
+/// XDG_MUSIC_DIR="/media/My \"Music\""
+/// XDG_VIDEOS_DIR="$HOME
+/// "#;
+/// let dirs = UserDirs::parse("user-dirs.dirs", content).unwrap();
+/// let desktop = dirs.get_location(LocationType::DesktopLocation).unwrap();
+/// assert_eq!(desktop.resolve("/home/user"), PathBuf::from("/home/user/Arbeitsfläche"));
+/// assert_eq!(dirs.get("MUSIC").unwrap().path(), PathBuf::from("/media/My \"Music\""));
+///
+/// let issues = dirs.issues().map(|err| err.to_string()).collect::<Vec<_>>();
+/// assert_eq!(issues, ["user-dirs.dirs:5: unterminated double quote"]);
+///
+/// let err = UserDirs::parse_with_strictness("user-dirs.dirs", content, Strictness::Strict)
+///     .unwrap_err();
+/// assert_eq!(err.to_string(), "user-dirs.dirs:5: unterminated double quote");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserDirs {
+    /// The file path.
+    path: PathBuf,
+    /// The parsed lines.
+    lines: Vec<Line>,
+    /// The numbers of the malformed lines and the reasons.
+    issues: Vec<(usize, String)>,
+}
+
+impl UserDirs {
+    /// Constructs a new empty [`UserDirs`] which would be saved to `path`.
+    pub fn new<P>(path: P) -> UserDirs
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirs {
+            path: path.into(),
+            lines: Vec::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Reads and parses the file at `path` keeping the malformed lines.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read.
+    pub fn load<P>(path: P) -> Result<UserDirs, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirs::load_with_strictness(path, Strictness::Lenient)
+    }
+
+    /// Reads and parses the file at `path`.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read
+    /// or, with [`Strictness::Strict`], [`Error::UserDirsParse`]
+    /// if any of its lines is malformed.
+    pub fn load_with_strictness<P>(path: P, strictness: Strictness) -> Result<UserDirs, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirs::load_from(&StdFileSystem, path.into(), strictness)
+    }
+
+    /// Reads and parses the file at `path` from the `fs` filesystem.
+    pub(crate) fn load_from(
+        fs: &dyn FileSystem,
+        path: PathBuf,
+        strictness: Strictness,
+    ) -> Result<UserDirs, Error> {
+        let content = read_file(fs, &path)?;
+        UserDirs::parse_with_strictness(path, &content, strictness)
+    }
+
+    /// Parses the `content` of a `user-dirs.dirs` file keeping the malformed lines.
+    ///
+    /// The `path` is only used to report the errors.
+    pub fn parse<P, C>(path: P, content: C) -> Result<UserDirs, Error>
+    where
+        P: Into<PathBuf>,
+        C: AsRef<[u8]>,
+    {
+        UserDirs::parse_with_strictness(path, content, Strictness::Lenient)
+    }
+
+    /// Parses the `content` of a `user-dirs.dirs` file.
+    ///
+    /// With [`Strictness::Lenient`] the malformed lines are kept verbatim
+    /// and reported by [`UserDirs::issues`], with [`Strictness::Strict`]
+    /// the first of them is returned as [`Error::UserDirsParse`].
+    ///
+    /// The paths are kept as raw bytes on Unix, so they are not required
+    /// to be valid UTF-8. On the other platforms such a line is malformed.
+    ///
+    /// The `path` is only used to report the errors.
+    pub fn parse_with_strictness<P, C>(
+        path: P,
+        content: C,
+        strictness: Strictness,
+    ) -> Result<UserDirs, Error>
+    where
+        P: Into<PathBuf>,
+        C: AsRef<[u8]>,
+    {
+        let path = path.into();
+        let mut lines = Vec::new();
+        let mut issues = Vec::new();
+        for (idx, line) in content.as_ref().split(|&b| b == b'\n').enumerate() {
+            lines.push(match parse_line(line) {
+                Ok(Some(entry)) => Line::Entry(entry, line.into()),
+                Ok(None) => Line::Other(line.into()),
+                Err(reason) if strictness == Strictness::Strict => {
+                    return Err(Error::UserDirsParse {
+                        path,
+                        line: idx + 1,
+                        reason,
+                    })
+                }
+                Err(reason) => {
+                    issues.push((idx + 1, reason));
+                    Line::Other(line.into())
+                }
+            });
+        }
+        // Drop the empty remainder after the trailing newline
+        if lines.last() == Some(&Line::Other(Vec::new())) {
+            lines.pop();
+        }
+        Ok(UserDirs {
+            path,
+            lines,
+            issues,
+        })
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns an iterator over the malformed lines kept by the lenient parsing
+    /// as [`Error::UserDirsParse`] errors.
+    pub fn issues(&self) -> impl Iterator<Item = Error> + '_ {
+        issue_errors(&self.path, &self.issues)
+    }
+
+    /// Returns an iterator over the entries in the order they appear in the file.
+    pub fn entries(&self) -> impl Iterator<Item = &UserDirEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry(entry, _) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Returns the entry with the `key`, e.g. `DESKTOP`.
+    ///
+    /// As in the shell the last assignment wins if the key is repeated.
+    pub fn get(&self, key: &str) -> Option<&UserDirEntry> {
+        self.entries().filter(|entry| entry.key == key).last()
+    }
+
+    /// Returns the entry of the user directory `location`.
+    ///
+    /// Returns [`None`] if the file has no such entry
+    /// or if the `location` is not a user directory.
+    pub fn get_location(&self, location: LocationType) -> Option<&UserDirEntry> {
+        self.get(user_dir_key(location)?)
+    }
+
+    /// Sets the entry with the `key` to the `path`.
+    ///
+    /// A path inside the `home` directory is written relative to `$HOME`
+    /// as `xdg-user-dirs-update` does. A relative `path` is considered
+    /// relative to the `home` directory.
+    ///
+    /// The existing entry is replaced in place, a new one is appended to the end.
+    ///
+    /// ### Example
+    /// ```
+    /// use standard_paths::UserDirs;
+    ///
+    /// let mut dirs = UserDirs::parse(
+    ///     "user-dirs.dirs",
+    ///     "# Comment\nXDG_DESKTOP_DIR=\"$HOME/Desktop\"\n",
+    /// )
+    /// .unwrap();
+    /// dirs.set("DESKTOP", "/home/user/Schreibtisch", "/home/user");
+    /// dirs.set("DOWNLOAD", "/mnt/downloads", "/home/user");
+    /// assert_eq!(
+    ///     dirs.to_string(),
+    ///     "# Comment\n\
+    ///      XDG_DESKTOP_DIR=\"$HOME/Schreibtisch\"\n\
+    ///      XDG_DOWNLOAD_DIR=\"/mnt/downloads\"\n"
+    /// );
+    /// ```
+    pub fn set<P, H>(&mut self, key: &str, path: P, home: H)
+    where
+        P: AsRef<Path>,
+        H: AsRef<Path>,
+    {
+        let entry = UserDirEntry::new(key, path.as_ref(), home.as_ref());
+        let line = entry.to_line();
+        let existing = self
+            .lines
+            .iter_mut()
+            .rev()
+            .find(|line| matches!(line, Line::Entry(entry, _) if entry.key == key));
+        match existing {
+            Some(existing) => *existing = Line::Entry(entry, line),
+            _ => self.lines.push(Line::Entry(entry, line)),
+        }
+    }
+
+    /// Sets the entry of the user directory `location` to the `path`.
+    ///
+    /// See [`UserDirs::set`] for details.
+    ///
+    /// Returns [`Error::UnsupportedLocation`] if the `location` is not a user directory.
+    pub fn set_location<P, H>(
+        &mut self,
+        location: LocationType,
+        path: P,
+        home: H,
+    ) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+        H: AsRef<Path>,
+    {
+        let key = user_dir_key(location).ok_or(Error::UnsupportedLocation(location))?;
+        self.set(key, path, home);
+        Ok(())
+    }
+
+    /// Writes the contents to the file atomically.
+    ///
+    /// The contents are written to a temporary file in the same directory first
+    /// which then replaces the original file. The parent directory is created
+    /// if it does not exist.
+    pub fn save(&self) -> Result<(), Error> {
+        self.save_to(&StdFileSystem)
+    }
+
+    /// Writes the contents to the file atomically on the `fs` filesystem.
+    pub(crate) fn save_to(&self, fs: &dyn FileSystem) -> Result<(), Error> {
+        let io_err = |path: &Path, source: io::Error| Error::Io {
+            location: None,
+            path: Some(path.into()),
+            source,
+        };
+        if let Some(parent) = self.path.parent() {
+            fs.create_dir_all(parent)
+                .map_err(|err| io_err(parent, err))?;
+        }
+        fs.write_atomic(&self.path, &self.to_bytes())
+            .map_err(|err| io_err(&self.path, err))
+    }
+
+    /// Returns the contents as they are written to the file.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::new();
+        for line in &self.lines {
+            match line {
+                Line::Other(line) | Line::Entry(_, line) => res.extend_from_slice(line),
+            }
+            res.push(b'\n');
+        }
+        res
+    }
+}
+
+impl fmt::Display for UserDirs {
+    /// Formats the contents as they are written to the file
+    /// replacing the invalid UTF-8 sequences.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// The contents of a `user-dirs.defaults` file.
+///
+/// The file is installed by the distribution or the administrator,
+/// usually as `/etc/xdg/user-dirs.defaults`, and defines the names of the user
+/// directories used when the user has no `user-dirs.dirs` file yet.
+/// Every line has the `<KEY>=<path>` form where the path is relative
+/// to the home directory.
+///
+/// ### Example
+/// ```
+/// use standard_paths::{LocationType, UserDirDefaults};
+/// use std::path::PathBuf;
+///
+/// let content = "## Default settings\nDESKTOP=Desktop\nDOWNLOAD=Desktop/Downloads\n";
+/// let defaults = UserDirDefaults::parse("user-dirs.defaults", content).unwrap();
+/// let download = defaults.get_location(LocationType::DownloadLocation).unwrap();
+/// assert_eq!(download.resolve("/home/user"), PathBuf::from("/home/user/Desktop/Downloads"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserDirDefaults {
+    /// The file path.
+    path: PathBuf,
+    /// The entries in the order they appear in the file.
+    entries: Vec<UserDirEntry>,
+    /// The numbers of the malformed lines and the reasons.
+    issues: Vec<(usize, String)>,
+}
+
+impl UserDirDefaults {
+    /// Reads and parses the file at `path` skipping the malformed lines.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read.
+    pub fn load<P>(path: P) -> Result<UserDirDefaults, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirDefaults::load_with_strictness(path, Strictness::Lenient)
+    }
+
+    /// Reads and parses the file at `path`.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read
+    /// or, with [`Strictness::Strict`], [`Error::UserDirsParse`]
+    /// if any of its lines is malformed.
+    pub fn load_with_strictness<P>(
+        path: P,
+        strictness: Strictness,
+    ) -> Result<UserDirDefaults, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirDefaults::load_from(&StdFileSystem, path.into(), strictness)
+    }
+
+    /// Reads and parses the file at `path` from the `fs` filesystem.
+    pub(crate) fn load_from(
+        fs: &dyn FileSystem,
+        path: PathBuf,
+        strictness: Strictness,
+    ) -> Result<UserDirDefaults, Error> {
+        let content = read_file(fs, &path)?;
+        UserDirDefaults::parse_with_strictness(path, content, strictness)
+    }
+
+    /// Parses the `content` of a `user-dirs.defaults` file skipping the malformed lines.
+    ///
+    /// The `path` is only used to report the errors.
+    pub fn parse<P, C>(path: P, content: C) -> Result<UserDirDefaults, Error>
+    where
+        P: Into<PathBuf>,
+        C: AsRef<[u8]>,
+    {
+        UserDirDefaults::parse_with_strictness(path, content, Strictness::Lenient)
+    }
+
+    /// Parses the `content` of a `user-dirs.defaults` file.
+    ///
+    /// With [`Strictness::Lenient`] the malformed lines are skipped
+    /// and reported by [`UserDirDefaults::issues`], with [`Strictness::Strict`]
+    /// the first of them is returned as [`Error::UserDirsParse`].
+    ///
+    /// The `path` is only used to report the errors.
+    pub fn parse_with_strictness<P, C>(
+        path: P,
+        content: C,
+        strictness: Strictness,
+    ) -> Result<UserDirDefaults, Error>
+    where
+        P: Into<PathBuf>,
+        C: AsRef<[u8]>,
+    {
+        let path = path.into();
+        let mut entries = Vec::new();
+        let issues = parse_key_values(&path, content.as_ref(), strictness, |key, value| {
+            let path = PathBuf::from(value);
+            entries.push(UserDirEntry {
+                key: key.into(),
+                home_relative: path.is_relative(),
+                path,
+            });
+            Ok(())
+        })?;
+        Ok(UserDirDefaults {
+            path,
+            entries,
+            issues,
+        })
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns an iterator over the malformed lines skipped by the lenient parsing
+    /// as [`Error::UserDirsParse`] errors.
+    pub fn issues(&self) -> impl Iterator<Item = Error> + '_ {
+        issue_errors(&self.path, &self.issues)
+    }
+
+    /// Returns an iterator over the entries in the order they appear in the file.
+    pub fn entries(&self) -> impl Iterator<Item = &UserDirEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the entry with the `key`, e.g. `DESKTOP`.
+    pub fn get(&self, key: &str) -> Option<&UserDirEntry> {
+        self.entries.iter().rev().find(|entry| entry.key == key)
+    }
+
+    /// Returns the entry of the user directory `location`.
+    ///
+    /// Returns [`None`] if the file has no such entry
+    /// or if the `location` is not a user directory.
+    pub fn get_location(&self, location: LocationType) -> Option<&UserDirEntry> {
+        self.get(user_dir_key(location)?)
+    }
+}
+
+/// The settings from a `user-dirs.conf` file.
+///
+/// The file is looked up in the user configuration directory first
+/// and then in the system ones and has the following keys:
+/// * `enabled` - `False` disables the management of the user directories,
+///   so the `user-dirs.defaults` are not applied;
+/// * `filename_encoding` - the encoding of the directory names,
+///   either `UTF-8` or `locale`.
+///
+/// ### Example
+/// ```
+/// use standard_paths::UserDirsConfig;
+///
+/// let config = UserDirsConfig::parse("user-dirs.conf", "enabled=False\n").unwrap();
+/// assert!(!config.is_enabled());
+/// assert_eq!(config.filename_encoding(), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDirsConfig {
+    /// The file path.
+    path: PathBuf,
+    /// The `enabled` setting.
+    enabled: bool,
+    /// The `filename_encoding` setting.
+    filename_encoding: Option<String>,
+    /// The numbers of the malformed lines and the reasons.
+    issues: Vec<(usize, String)>,
+}
+
+impl Default for UserDirsConfig {
+    fn default() -> Self {
+        UserDirsConfig {
+            path: PathBuf::new(),
+            enabled: true,
+            filename_encoding: None,
+            issues: Vec::new(),
+        }
+    }
+}
+
+impl UserDirsConfig {
+    /// Reads and parses the file at `path` skipping the malformed lines.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read.
+    pub fn load<P>(path: P) -> Result<UserDirsConfig, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirsConfig::load_with_strictness(path, Strictness::Lenient)
+    }
+
+    /// Reads and parses the file at `path`.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read
+    /// or, with [`Strictness::Strict`], [`Error::UserDirsParse`]
+    /// if any of its lines is malformed.
+    pub fn load_with_strictness<P>(path: P, strictness: Strictness) -> Result<UserDirsConfig, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        UserDirsConfig::load_from(&StdFileSystem, path.into(), strictness)
+    }
+
+    /// Reads and parses the file at `path` from the `fs` filesystem.
+    pub(crate) fn load_from(
+        fs: &dyn FileSystem,
+        path: PathBuf,
+        strictness: Strictness,
+    ) -> Result<UserDirsConfig, Error> {
+        let content = read_file(fs, &path)?;
+        UserDirsConfig::parse_with_strictness(path, content, strictness)
+    }
+
+    /// Parses the `content` of a `user-dirs.conf` file skipping the malformed lines.
+    ///
+    /// The `path` is only used to report the errors. The unknown keys are ignored.
+    pub fn parse<P, C>(path: P, content: C) -> Result<UserDirsConfig, Error>
+    where
+        P: Into<PathBuf>,
+        C: AsRef<[u8]>,
+    {
+        UserDirsConfig::parse_with_strictness(path, content, Strictness::Lenient)
+    }
+
+    /// Parses the `content` of a `user-dirs.conf` file.
+    ///
+    /// With [`Strictness::Lenient`] the malformed lines, e.g. an invalid `enabled` value,
+    /// are skipped and reported by [`UserDirsConfig::issues`], with [`Strictness::Strict`]
+    /// the first of them is returned as [`Error::UserDirsParse`].
+    ///
+    /// The `path` is only used to report the errors. The unknown keys are ignored.
+    pub fn parse_with_strictness<P, C>(
+        path: P,
+        content: C,
+        strictness: Strictness,
+    ) -> Result<UserDirsConfig, Error>
+    where
+        P: Into<PathBuf>,
+        C: AsRef<[u8]>,
+    {
+        let path = path.into();
+        let mut enabled = true;
+        let mut filename_encoding = None;
+        let issues = parse_key_values(&path, content.as_ref(), strictness, |key, value| {
+            match key {
+                "enabled" => {
+                    enabled = if value.eq_ignore_ascii_case("true") {
+                        true
+                    } else if value.eq_ignore_ascii_case("false") {
+                        false
+                    } else {
+                        return Err(format!("expected 'True' or 'False', found '{value}'"));
+                    }
+                }
+                "filename_encoding" => filename_encoding = Some(value.into()),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(UserDirsConfig {
+            path,
+            enabled,
+            filename_encoding,
+            issues,
+        })
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns an iterator over the malformed lines skipped by the lenient parsing
+    /// as [`Error::UserDirsParse`] errors.
+    pub fn issues(&self) -> impl Iterator<Item = Error> + '_ {
+        issue_errors(&self.path, &self.issues)
+    }
+
+    /// Returns `false` if the management of the user directories is disabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the encoding of the directory names if it's set.
+    pub fn filename_encoding(&self) -> Option<&str> {
+        self.filename_encoding.as_deref()
+    }
+
+    /// Returns `true` if the directory names are encoded in UTF-8.
+    ///
+    /// # Arguments
+    /// * `codeset` - the codeset of the current locale, e.g. `UTF-8`,
+    ///   used if the encoding is set to `locale`.
+    pub fn is_utf8(&self, codeset: Option<&str>) -> bool {
+        let is_utf8 =
+            |name: &str| name.eq_ignore_ascii_case("utf-8") || name.eq_ignore_ascii_case("utf8");
+        match self.filename_encoding.as_deref() {
+            None => true,
+            Some(encoding) if encoding.eq_ignore_ascii_case("locale") => {
+                codeset.is_some_and(is_utf8)
+            }
+            Some(encoding) => is_utf8(encoding),
+        }
+    }
+}
+
+/// Reads the whole file at `path`.
+fn read_file(fs: &dyn FileSystem, path: &Path) -> Result<Vec<u8>, Error> {
+    fs.read(path).map_err(|source| Error::Io {
+        location: None,
+        path: Some(path.into()),
+        source,
+    })
+}
+
+/// Parses the `<key>=<value>` lines skipping the empty ones and the comments.
+///
+/// Returns the numbers of the malformed lines and the reasons
+/// unless the `strictness` is strict.
+fn parse_key_values<F>(
+    path: &Path,
+    content: &[u8],
+    strictness: Strictness,
+    mut f: F,
+) -> Result<Vec<(usize, String)>, Error>
+where
+    F: FnMut(&str, &str) -> Result<(), String>,
+{
+    let mut issues = Vec::new();
+    for (idx, line) in content.split(|&b| b == b'\n').enumerate() {
+        let res = match std::str::from_utf8(line).map(str::trim) {
+            Ok(line) if line.is_empty() || line.starts_with('#') => continue,
+            Ok(line) => match line.split_once('=') {
+                Some((key, value)) => f(key.trim(), value.trim()),
+                _ => Err(format!("expected '<key>=<value>', found '{line}'")),
+            },
+            Err(_) => Err("invalid UTF-8".into()),
+        };
+        match res {
+            Ok(()) => {}
+            Err(reason) if strictness == Strictness::Strict => {
+                return Err(Error::UserDirsParse {
+                    path: path.into(),
+                    line: idx + 1,
+                    reason,
+                })
+            }
+            Err(reason) => issues.push((idx + 1, reason)),
+        }
+    }
+    Ok(issues)
+}
+
+/// Converts the malformed lines of the file at `path` into errors.
+fn issue_errors<'a>(
+    path: &'a Path,
+    issues: &'a [(usize, String)],
+) -> impl Iterator<Item = Error> + 'a {
+    issues.iter().map(|(line, reason)| Error::UserDirsParse {
+        path: path.into(),
+        line: *line,
+        reason: reason.clone(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+impl StandardPaths {
+    /// Sets the user directories and updates the `user-dirs.dirs` file
+    /// like `xdg-user-dirs-update --set` does.
+    ///
+    /// The file is rewritten atomically keeping the other entries and the comments.
+    /// The file and the target directories are accessed through the instance
+    /// [`FileSystem`], so the changes are reflected by
+    /// [`StandardPaths::writable_location`] immediately.
+    ///
+    /// Returns [`Error::UserDirsParse`] if the existing file is malformed
+    /// and the instance [`Strictness`] is strict.
+    ///
+    /// # Arguments
+    /// * `dirs` - pairs of the user directory location types and the new paths;
+    ///   relative paths are considered relative to the home directory.
+    /// * `create` - whether to create the missing target directories.
+    ///
+    /// ### Example
+    /// ```no_run
+    /// use standard_paths::{LocationType, StandardPaths};
+    ///
+    /// let sp = StandardPaths::new("app", "org");
+    /// sp.set_user_dirs([(LocationType::DownloadLocation, "/mnt/data/Downloads")], true)
+    ///     .unwrap();
+    /// ```
+    pub fn set_user_dirs<I, P>(&self, dirs: I, create: bool) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (LocationType, P)>,
+        P: AsRef<Path>,
+    {
+        let dirs: Vec<(LocationType, P)> = dirs.into_iter().collect();
+        let location = match dirs.first() {
+            Some((location, _)) => *location,
+            _ => return Ok(()),
+        };
+        let env = self.sandboxed_environment();
+        let home = env.home_dir().ok_or(Error::HomeNotFound { location })?;
+        let file = user_dirs_file(&env, self.strictness(), location)?;
+        let fs = self.file_system();
+        let mut user_dirs = match UserDirs::load_from(fs, file.clone(), self.strictness()) {
+            Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                UserDirs::new(file)
+            }
+            res => res?,
+        };
+        for (location, path) in &dirs {
+            user_dirs.set_location(*location, path, &home)?;
+        }
+        if create {
+            for (location, path) in &dirs {
+                let path = home.join(path);
+                fs.create_dir_all(&path)
+                    .map_err(|err| Error::io(*location, &path, err))?;
+            }
+        }
+        user_dirs.save_to(fs)
+    }
+}
+
+/// Parses a single line returning [`None`] for the lines which are not user dir entries.
+fn parse_line(line: &[u8]) -> Result<Option<UserDirEntry>, String> {
+    let line = line.trim_ascii_start();
+    if line.is_empty() || line.starts_with(b"#") {
+        return Ok(None);
+    }
+    let (name, value) = match line.iter().position(|&b| b == b'=') {
+        Some(idx) => (&line[..idx], &line[idx + 1..]),
+        _ => {
+            return Err(format!(
+                "expected 'XDG_<NAME>_DIR=<path>', found '{}'",
+                String::from_utf8_lossy(line)
+            ))
+        }
+    };
+    let key = match name
+        .strip_prefix(b"XDG_")
+        .and_then(|name| name.strip_suffix(b"_DIR"))
+        .and_then(|key| std::str::from_utf8(key).ok())
+    {
+        Some(key) if !key.is_empty() => key,
+        _ => return Ok(None),
+    };
+
+    let (value, home_relative) = parse_value(value)?;
+    let path = path_from_bytes(value)?;
+    if !home_relative && !path.is_absolute() {
+        return Err(format!(
+            "'{}' is neither an absolute path nor relative to $HOME",
+            path.display()
+        ));
+    }
+    Ok(Some(UserDirEntry {
+        key: key.into(),
+        path,
+        home_relative,
+    }))
+}
+
+/// Unquotes a shell word and detects the leading `$HOME` variable.
+///
+/// Returns the value with the `$HOME` prefix stripped and whether it was present.
+fn parse_value(value: &[u8]) -> Result<(Vec<u8>, bool), String> {
+    let mut res = Vec::new();
+    // Whether the value starts with an unescaped `$`
+    let mut leading_dollar = false;
+    let mut bytes = value.iter();
+    while let Some(&b) = bytes.next() {
+        match b {
+            b'"' => loop {
+                match bytes.next() {
+                    Some(b'"') => break,
+                    Some(b'\\') => match bytes.next() {
+                        Some(&b @ (b'"' | b'\\' | b'$' | b'`')) => res.push(b),
+                        Some(b'\n') => {}
+                        Some(&b) => res.extend_from_slice(&[b'\\', b]),
+                        _ => return Err("unterminated double quote".into()),
+                    },
+                    Some(&b) => {
+                        if b == b'$' && res.is_empty() {
+                            leading_dollar = true;
+                        }
+                        res.push(b);
+                    }
+                    _ => return Err("unterminated double quote".into()),
+                }
+            },
+            b'\'' => loop {
+                match bytes.next() {
+                    Some(b'\'') => break,
+                    Some(&b) => res.push(b),
+                    _ => return Err("unterminated single quote".into()),
+                }
+            },
+            b'\\' => match bytes.next() {
+                Some(&b) => res.push(b),
+                _ => return Err("trailing backslash".into()),
+            },
+            b if b.is_ascii_whitespace() => {
+                let rest = bytes.as_slice().trim_ascii_start();
+                if rest.is_empty() || rest.starts_with(b"#") {
+                    break;
+                }
+                return Err(format!(
+                    "unexpected '{}' after the value",
+                    String::from_utf8_lossy(rest)
+                ));
+            }
+            b => {
+                if b == b'$' && res.is_empty() {
+                    leading_dollar = true;
+                }
+                res.push(b);
+            }
+        }
+    }
+
+    if leading_dollar {
+        for var in [&b"$HOME"[..], b"${HOME}"] {
+            if let Some(rest) = res.strip_prefix(var) {
+                if rest.is_empty() || rest.starts_with(b"/") {
+                    let start = rest.iter().take_while(|&&b| b == b'/').count();
+                    return Ok((rest[start..].to_vec(), true));
+                }
+            }
+        }
+    }
+    Ok((res, false))
+}
+
+/// Converts the raw bytes of a path read from a file into a [`PathBuf`].
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> Result<PathBuf, String> {
+    use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+
+    Ok(OsString::from_vec(bytes).into())
+}
+
+/// Converts the raw bytes of a path read from a file into a [`PathBuf`].
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> Result<PathBuf, String> {
+    String::from_utf8(bytes)
+        .map(PathBuf::from)
+        .map_err(|_| "invalid UTF-8".into())
+}
+
+/// Returns the raw bytes of the `path` to write into a file.
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+/// Returns the raw bytes of the `path` to write into a file.
+#[cfg(not(unix))]
+fn path_to_bytes(path: &Path) -> Cow<'_, [u8]> {
+    Cow::Owned(path.to_string_lossy().into_owned().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TempDir, MemoryFileSystem};
+    use std::fs;
+
+    fn value(value: &str) -> (String, bool) {
+        let (value, home_relative) = parse_value(value.as_bytes()).unwrap();
+        (String::from_utf8(value).unwrap(), home_relative)
+    }
+
+    #[test]
+    fn quoting_and_escapes() {
+        assert_eq!(value(r#""/a b""#), ("/a b".into(), false));
+        assert_eq!(value(r"'/a \b'"), (r"/a \b".into(), false));
+        assert_eq!(value(r"/a\ b"), ("/a b".into(), false));
+        assert_eq!(
+            value(r#""/a\"b\\c\$d\`e\f""#),
+            (r#"/a"b\c$d`e\f"#.into(), false)
+        );
+        assert_eq!(value(r#""/a"'/b'/c"#), ("/a/b/c".into(), false));
+        assert_eq!(value(r#""/a" # comment"#), ("/a".into(), false));
+        assert_eq!(
+            parse_value(r#""/a" /b"#.as_bytes()).unwrap_err(),
+            "unexpected '/b' after the value"
+        );
+        assert_eq!(
+            parse_value("'/a".as_bytes()).unwrap_err(),
+            "unterminated single quote"
+        );
+        assert_eq!(
+            parse_value(r"/a\".as_bytes()).unwrap_err(),
+            "trailing backslash"
+        );
+    }
+
+    #[test]
+    fn home_relative_values() {
+        assert_eq!(value(r#""$HOME/Desktop""#), ("Desktop".into(), true));
+        assert_eq!(value(r#""${HOME}/Desktop""#), ("Desktop".into(), true));
+        assert_eq!(value("$HOME"), (String::new(), true));
+        assert_eq!(value("$HOME//a/b"), ("a/b".into(), true));
+        // Only an unescaped leading variable is expanded
+        assert_eq!(value(r#""\$HOME/a""#), ("$HOME/a".into(), false));
+        assert_eq!(value("$HOMEDIR/a"), ("$HOMEDIR/a".into(), false));
+        assert_eq!(value("/x/$HOME"), ("/x/$HOME".into(), false));
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(parse_line("  # XDG_DESKTOP_DIR=/a".as_bytes()), Ok(None));
+        assert_eq!(parse_line("OTHER=value".as_bytes()), Ok(None));
+        assert_eq!(parse_line("XDG__DIR=/a".as_bytes()), Ok(None));
+        let entry = parse_line(r#"XDG_DESKTOP_DIR="$HOME/Desktop""#.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.key(), "DESKTOP");
+        assert!(entry.is_home_relative());
+        assert_eq!(entry.resolve("/home/user"), Path::new("/home/user/Desktop"));
+        assert_eq!(
+            parse_line("XDG_DESKTOP_DIR=Desktop".as_bytes()).unwrap_err(),
+            "'Desktop' is neither an absolute path nor relative to $HOME"
+        );
+        assert!(parse_line("stray".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn stray_lines() {
+        let content = "XDG_DESKTOP_DIR=\"$HOME/Desktop\"\nstray\nXDG_MUSIC_DIR=Music\n\
+                       XDG_VIDEOS_DIR=/media/videos\n";
+        let dirs = UserDirs::parse("user-dirs.dirs", content).unwrap();
+        let keys = dirs.entries().map(UserDirEntry::key).collect::<Vec<_>>();
+        assert_eq!(keys, ["DESKTOP", "VIDEOS"]);
+        let issues = dirs.issues().map(|err| err.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            [
+                "user-dirs.dirs:2: expected 'XDG_<NAME>_DIR=<path>', found 'stray'",
+                "user-dirs.dirs:3: 'Music' is neither an absolute path nor relative to $HOME",
+            ]
+        );
+        // The malformed lines are preserved
+        assert_eq!(dirs.to_string(), content);
+
+        match UserDirs::parse_with_strictness("user-dirs.dirs", content, Strictness::Strict) {
+            Err(Error::UserDirsParse { line, .. }) => assert_eq!(line, 2),
+            res => panic!("unexpected result: {res:?}"),
+        }
+        let dirs = UserDirs::parse_with_strictness(
+            "user-dirs.dirs",
+            "# Comment\n\nXDG_DESKTOP_DIR=/a\n",
+            Strictness::Strict,
+        )
+        .unwrap();
+        assert_eq!(dirs.issues().count(), 0);
+    }
+
+    #[test]
+    fn last_assignment_wins() {
+        let mut dirs =
+            UserDirs::parse("user-dirs.dirs", "XDG_DESKTOP_DIR=/a\nXDG_DESKTOP_DIR=/b\n").unwrap();
+        assert_eq!(dirs.get("DESKTOP").unwrap().path(), Path::new("/b"));
+        dirs.set("DESKTOP", "/home/user/c", "/home/user");
+        assert_eq!(
+            dirs.to_string(),
+            "XDG_DESKTOP_DIR=/a\nXDG_DESKTOP_DIR=\"$HOME/c\"\n"
+        );
+        assert!(matches!(
+            dirs.set_location(LocationType::HomeLocation, "/a", "/home/user"),
+            Err(Error::UnsupportedLocation(LocationType::HomeLocation))
+        ));
+    }
+
+    #[test]
+    fn written_lines_round_trip() {
+        let mut dirs = UserDirs::new("user-dirs.dirs");
+        dirs.set("MUSIC", "/home/user/My \"$Music\"", "/home/user");
+        dirs.set("VIDEOS", r"/media/a\b`c", "/home/user");
+        dirs.set("DESKTOP", "Desktop", "/home/user");
+        let content = dirs.to_string();
+        assert_eq!(
+            content,
+            "XDG_MUSIC_DIR=\"$HOME/My \\\"\\$Music\\\"\"\n\
+             XDG_VIDEOS_DIR=\"/media/a\\\\b\\`c\"\n\
+             XDG_DESKTOP_DIR=\"$HOME/Desktop\"\n"
+        );
+        let parsed = UserDirs::parse("user-dirs.dirs", &content).unwrap();
+        let resolve = |key| parsed.get(key).unwrap().resolve("/home/user");
+        assert_eq!(resolve("MUSIC"), Path::new("/home/user/My \"$Music\""));
+        assert_eq!(resolve("VIDEOS"), Path::new(r"/media/a\b`c"));
+        assert_eq!(resolve("DESKTOP"), Path::new("/home/user/Desktop"));
+    }
+
+    #[test]
+    fn save() {
+        let dir = TempDir::new();
+        let path = dir.join("config/user-dirs.dirs");
+        let mut dirs = UserDirs::new(&path);
+        dirs.set("DESKTOP", "/home/user/Desktop", "/home/user");
+        dirs.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "XDG_DESKTOP_DIR=\"$HOME/Desktop\"\n"
+        );
+
+        let mut dirs = UserDirs::load(&path).unwrap();
+        dirs.set("DOWNLOAD", "/mnt/downloads", "/home/user");
+        dirs.save().unwrap();
+        assert_eq!(UserDirs::load(&path).unwrap(), dirs);
+        // No temporary files are left behind
+        let names = fs::read_dir(dir.join("config"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["user-dirs.dirs"]);
+
+        match UserDirs::new(dir.join("config/user-dirs.dirs/nested")).save() {
+            Err(Error::Io { path: Some(_), .. }) => {}
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn set_user_dirs() {
+        use crate::MapEnvironment;
+
+        let home = TempDir::new();
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::write(
+            home.join(".config/user-dirs.dirs"),
+            "# Comment\nXDG_MUSIC_DIR=\"$HOME/Music\"\n",
+        )
+        .unwrap();
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home(home.path()))
+            .build();
+
+        sp.set_user_dirs(
+            [
+                (LocationType::MusicLocation, PathBuf::from("Klänge")),
+                (LocationType::DownloadLocation, home.join("Laden")),
+            ],
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(home.join(".config/user-dirs.dirs")).unwrap(),
+            "# Comment\nXDG_MUSIC_DIR=\"$HOME/Klänge\"\nXDG_DOWNLOAD_DIR=\"$HOME/Laden\"\n"
+        );
+        assert!(home.join("Klänge").is_dir());
+        assert!(home.join("Laden").is_dir());
+        assert_eq!(
+            sp.writable_location(LocationType::MusicLocation).unwrap(),
+            home.join("Klänge")
+        );
+
+        sp.set_user_dirs([(LocationType::DesktopLocation, "Work")], false)
+            .unwrap();
+        assert!(!home.join("Work").exists());
+        assert!(matches!(
+            sp.set_user_dirs([(LocationType::HomeLocation, "/a")], false),
+            Err(Error::UnsupportedLocation(LocationType::HomeLocation))
+        ));
+        sp.set_user_dirs(Vec::<(LocationType, &str)>::new(), true)
+            .unwrap();
+    }
+
+    #[test]
+    fn defaults() {
+        let content = "# Comment\n DESKTOP = Desktop \nMUSIC=/srv/music\nDESKTOP=Work\nOTHER=x\n";
+        let defaults = UserDirDefaults::parse("user-dirs.defaults", content).unwrap();
+        let keys = defaults
+            .entries()
+            .map(UserDirEntry::key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["DESKTOP", "MUSIC", "DESKTOP", "OTHER"]);
+        let resolve = |location| {
+            defaults
+                .get_location(location)
+                .map(|entry| entry.resolve("/home/user"))
+        };
+        assert_eq!(
+            resolve(LocationType::DesktopLocation),
+            Some(PathBuf::from("/home/user/Work"))
+        );
+        assert_eq!(
+            resolve(LocationType::MusicLocation),
+            Some(PathBuf::from("/srv/music"))
+        );
+        assert_eq!(resolve(LocationType::MoviesLocation), None);
+        assert_eq!(resolve(LocationType::HomeLocation), None);
+
+        let content = "DESKTOP=Desktop\nMUSIC\nVIDEOS=Videos\n";
+        let defaults = UserDirDefaults::parse("user-dirs.defaults", content).unwrap();
+        assert_eq!(defaults.entries().count(), 2);
+        let issues = defaults
+            .issues()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            ["user-dirs.defaults:2: expected '<key>=<value>', found 'MUSIC'"]
+        );
+        let err = UserDirDefaults::parse_with_strictness(
+            "user-dirs.defaults",
+            content,
+            Strictness::Strict,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), issues[0]);
+    }
+
+    #[test]
+    fn config() {
+        let parse = |content| UserDirsConfig::parse("user-dirs.conf", content);
+        let config = parse("# Comment\n\nunknown=1\n").unwrap();
+        assert_eq!(config.path(), Path::new("user-dirs.conf"));
+        assert!(config.is_enabled());
+        assert!(config.is_utf8(None));
+        assert_eq!(config.issues().count(), 0);
+
+        let config = parse("enabled=TRUE\nfilename_encoding=locale\n").unwrap();
+        assert!(config.is_enabled());
+        assert_eq!(config.filename_encoding(), Some("locale"));
+        assert!(config.is_utf8(Some("utf8")));
+        assert!(!config.is_utf8(Some("ISO-8859-1")));
+        assert!(!config.is_utf8(None));
+
+        let config = parse("enabled = false\nfilename_encoding=UTF-8\n").unwrap();
+        assert!(!config.is_enabled());
+        assert!(config.is_utf8(Some("ISO-8859-1")));
+        assert!(!parse("filename_encoding=latin1\n").unwrap().is_utf8(None));
+
+        // The malformed values do not change the settings
+        let content = "enabled=False\nenabled=yes\n";
+        let config = parse(content).unwrap();
+        assert!(!config.is_enabled());
+        assert_eq!(config.issues().count(), 1);
+        match UserDirsConfig::parse_with_strictness("user-dirs.conf", content, Strictness::Strict) {
+            Err(Error::UserDirsParse { line, reason, .. }) => {
+                assert_eq!(line, 2);
+                assert_eq!(reason, "expected 'True' or 'False', found 'yes'");
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn load_from_file_system() {
+        let fs = MemoryFileSystem::new()
+            .with_file_content("/etc/xdg/user-dirs.conf", "enabled=False\n")
+            .with_file_content(
+                "/etc/xdg/user-dirs.defaults",
+                b"DESKTOP=Desktop\nMUSIC=\xff\n",
+            );
+        let load_config =
+            |path: &str| UserDirsConfig::load_from(&fs, path.into(), Strictness::Lenient);
+        let config = load_config("/etc/xdg/user-dirs.conf").unwrap();
+        assert!(!config.is_enabled());
+        let path = PathBuf::from("/etc/xdg/user-dirs.defaults");
+        let defaults = UserDirDefaults::load_from(&fs, path.clone(), Strictness::Lenient).unwrap();
+        assert_eq!(defaults.entries().count(), 1);
+        match UserDirDefaults::load_from(&fs, path, Strictness::Strict) {
+            Err(Error::UserDirsParse { line, reason, .. }) => {
+                assert_eq!(line, 2);
+                assert_eq!(reason, "invalid UTF-8");
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+        match load_config("/etc/user-dirs.conf") {
+            Err(Error::Io { source, .. }) => assert_eq!(source.kind(), io::ErrorKind::NotFound),
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn set_user_dirs_through_file_system() {
+        use crate::MapEnvironment;
+
+        let fs = MemoryFileSystem::new()
+            .with_file_content("/home/user/.config/user-dirs.dirs", "stray\n")
+            .with_mode("/home/user/.config/user-dirs.dirs", 0o600);
+        let builder = |strictness| {
+            StandardPaths::builder()
+                .environment(MapEnvironment::new().with_home("/home/user"))
+                .file_system(fs.clone())
+                .strictness(strictness)
+        };
+
+        let sp = builder(Strictness::Strict).build();
+        assert!(matches!(
+            sp.set_user_dirs([(LocationType::MusicLocation, "Klänge")], false),
+            Err(Error::UserDirsParse { line: 1, .. })
+        ));
+
+        let sp = builder(Strictness::Lenient).build();
+        sp.set_user_dirs([(LocationType::MusicLocation, "Klänge")], true)
+            .unwrap();
+        assert_eq!(
+            sp.writable_location(LocationType::MusicLocation).unwrap(),
+            Path::new("/home/user/Klänge")
+        );
+        let fs = sp.file_system();
+        assert!(fs.is_dir(Path::new("/home/user/Klänge")));
+        let file = Path::new("/home/user/.config/user-dirs.dirs");
+        assert_eq!(
+            fs.read(file).unwrap(),
+            "stray\nXDG_MUSIC_DIR=\"$HOME/Klänge\"\n".as_bytes()
+        );
+        assert_eq!(fs.metadata(file).unwrap().mode(), 0o600);
+
+        // The sandbox file is used in the test mode
+        let sp = StandardPaths::builder()
+            .environment(MapEnvironment::new().with_home("/home/user"))
+            .file_system(MemoryFileSystem::new())
+            .test_root("/sandbox")
+            .build();
+        sp.set_user_dirs([(LocationType::DesktopLocation, "Work")], false)
+            .unwrap();
+        let desktop = sp.writable_location(LocationType::DesktopLocation).unwrap();
+        assert!(desktop.starts_with("/sandbox"));
+        assert!(desktop.ends_with("Work"));
+        assert!(!sp.file_system().exists(&desktop));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let entries: &[u8] = b"XDG_MUSIC_DIR=\"$HOME/M\xfcsik\"\nXDG_VIDEOS_DIR=/media/\xff\n";
+        let content = [b"# \xe4\n\xff stray\n", entries].concat();
+        let dirs = UserDirs::parse("user-dirs.dirs", content).unwrap();
+        assert_eq!(
+            dirs.get("MUSIC").unwrap().resolve("/home/user"),
+            Path::new("/home/user").join(OsStr::from_bytes(b"M\xfcsik"))
+        );
+        assert_eq!(
+            dirs.get("VIDEOS").unwrap().path(),
+            Path::new(OsStr::from_bytes(b"/media/\xff"))
+        );
+        assert_eq!(dirs.issues().count(), 1);
+
+        // The content is written back unchanged
+        let fs = MemoryFileSystem::new();
+        let mut dirs =
+            UserDirs::parse_with_strictness("/config/user-dirs.dirs", entries, Strictness::Strict)
+                .unwrap();
+        dirs.save_to(&fs).unwrap();
+        let file = Path::new("/config/user-dirs.dirs");
+        assert_eq!(fs.read(file).unwrap(), entries);
+        dirs.set(
+            "DESKTOP",
+            Path::new(OsStr::from_bytes(b"/home/user/\xe4$")),
+            "/home/user",
+        );
+        dirs.save_to(&fs).unwrap();
+        let saved = fs.read(file).unwrap();
+        assert!(saved.ends_with(b"XDG_DESKTOP_DIR=\"$HOME/\xe4\\$\"\n"));
+        let dirs = UserDirs::load_from(&fs, file.into(), Strictness::Strict).unwrap();
+        assert_eq!(
+            dirs.get("DESKTOP").unwrap().path(),
+            Path::new(OsStr::from_bytes(b"\xe4$"))
+        );
+    }
+}
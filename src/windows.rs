@@ -1,9 +1,9 @@
 use std::{
     env,
     ffi::{OsStr, OsString},
-    io::{Error, ErrorKind},
+    io::{self, ErrorKind},
     os::windows::ffi::{OsStrExt, OsStringExt},
-    path::PathBuf,
+    path::{Path, PathBuf},
     ptr, slice,
 };
 use winapi::{
@@ -14,7 +14,7 @@ use winapi::{
     },
 };
 
-use crate::{LocationType, StandardPaths};
+use crate::{Error, FileSystem, LocationType, StandardPaths};
 
 /// [`FOLDERID_Desktop`](https://msdn.microsoft.com/en-us/library/dd378457.aspx#FOLDERID_Desktop)
 #[allow(non_upper_case_globals)]
@@ -88,6 +88,33 @@ const FOLDERID_Downloads: GUID = GUID {
     Data4: [0x91, 0x64, 0x39, 0xC4, 0x92, 0x5E, 0x46, 0x7B],
 };
 
+/// [`FOLDERID_Templates`](https://msdn.microsoft.com/en-us/library/dd378457.aspx#FOLDERID_Templates)
+#[allow(non_upper_case_globals)]
+const FOLDERID_Templates: GUID = GUID {
+    Data1: 0xA63293E8,
+    Data2: 0x664E,
+    Data3: 0x48DB,
+    Data4: [0xA0, 0x79, 0xDF, 0x75, 0x9E, 0x05, 0x09, 0xF7],
+};
+
+/// [`FOLDERID_Public`](https://msdn.microsoft.com/en-us/library/dd378457.aspx#FOLDERID_Public)
+#[allow(non_upper_case_globals)]
+const FOLDERID_Public: GUID = GUID {
+    Data1: 0xDFDF76A2,
+    Data2: 0xC82A,
+    Data3: 0x4D63,
+    Data4: [0x90, 0x6A, 0x56, 0x44, 0xAC, 0x45, 0x73, 0x85],
+};
+
+/// [`FOLDERID_UserProgramFiles`](https://msdn.microsoft.com/en-us/library/dd378457.aspx#FOLDERID_UserProgramFiles)
+#[allow(non_upper_case_globals)]
+const FOLDERID_UserProgramFiles: GUID = GUID {
+    Data1: 0x5CD7AEE2,
+    Data2: 0x2219,
+    Data3: 0x4A67,
+    Data4: [0xB8, 0x5D, 0x6C, 0x9C, 0xE1, 0x56, 0x60, 0xCB],
+};
+
 /// [`FOLDERID_LocalAppData`](https://msdn.microsoft.com/en-us/library/dd378457.aspx#FOLDERID_LocalAppData)
 #[allow(non_upper_case_globals)]
 const FOLDERID_LocalAppData: GUID = GUID {
@@ -169,8 +196,54 @@ macro_rules! sh_get_known_folder_path {
 }
 
 impl StandardPaths {
+    /// Returns the test mode sandbox counterpart of the `location`.
+    fn sandboxed_location(&self, root: PathBuf, location: LocationType) -> PathBuf {
+        let mut path = root;
+        match location {
+            LocationType::HomeLocation => path.push("home"),
+            LocationType::DesktopLocation => path.push("home/Desktop"),
+            LocationType::DocumentsLocation => path.push("home/Documents"),
+            LocationType::DownloadLocation => path.push("home/Downloads"),
+            LocationType::MoviesLocation => path.push("home/Videos"),
+            LocationType::MusicLocation => path.push("home/Music"),
+            LocationType::PicturesLocation => path.push("home/Pictures"),
+            LocationType::TemplatesLocation => path.push("home/Templates"),
+            LocationType::PublicShareLocation => path.push("home/Public"),
+            LocationType::RuntimeLocation => path.push("runtime"),
+            LocationType::TempLocation => path.push("tmp"),
+            LocationType::UserExecutablesLocation => path.push("bin"),
+            LocationType::FontsLocation => path.push("share/fonts"),
+            LocationType::ApplicationsLocation => path.push("share/applications"),
+            LocationType::GenericCacheLocation | LocationType::AppCacheLocation => {
+                path.push("cache")
+            }
+            LocationType::GenericStateLocation | LocationType::AppStateLocation => {
+                path.push("state")
+            }
+            LocationType::GenericDataLocation
+            | LocationType::AppDataLocation
+            | LocationType::AppLocalDataLocation => path.push("share"),
+            LocationType::ConfigLocation
+            | LocationType::GenericConfigLocation
+            | LocationType::AppConfigLocation => path.push("config"),
+        }
+        if location == LocationType::AppCacheLocation
+            || location == LocationType::AppDataLocation
+            || location == LocationType::AppLocalDataLocation
+            || location == LocationType::AppConfigLocation
+            || location == LocationType::ConfigLocation
+            || location == LocationType::AppStateLocation
+        {
+            self.append_organization_and_app(&mut path);
+        }
+        path
+    }
+
     #[inline]
     pub(super) fn writable_location_impl(&self, location: LocationType) -> Result<PathBuf, Error> {
+        if let Some(root) = self.test_root() {
+            return Ok(self.sandboxed_location(root, location));
+        }
         match location {
             LocationType::DownloadLocation => {
                 sh_get_known_folder_path!(FOLDERID_Downloads, path, { Ok(path) }, {
@@ -191,15 +264,32 @@ impl StandardPaths {
                 Ok(path)
             }
 
-            LocationType::RuntimeLocation | LocationType::HomeLocation => {
-                home::home_dir().ok_or_else(StandardPaths::home_dir_err)
+            LocationType::AppStateLocation | LocationType::GenericStateLocation => {
+                // Follow Qt and keep the state next to the cache in the local AppData directory.
+                let loc2 = if location == LocationType::AppStateLocation {
+                    LocationType::AppLocalDataLocation
+                } else {
+                    LocationType::GenericDataLocation
+                };
+                let mut path = self.writable_location(loc2)?;
+                path.push("State");
+                Ok(path)
             }
 
+            LocationType::RuntimeLocation | LocationType::HomeLocation => self
+                .environment()
+                .home_dir()
+                .ok_or(Error::HomeNotFound { location }),
+
             LocationType::TempLocation => {
-                let canonicalized = env::temp_dir().canonicalize().unwrap();
-                Ok(PathBuf::from(
-                    canonicalized.to_str().unwrap().get(4..).unwrap(),
-                ))
+                // The temporary directory is returned as is if it cannot be resolved
+                let temp = self.environment().temp_dir();
+                Ok(self
+                    .file_system()
+                    .canonicalize(&temp)
+                    .ok()
+                    .and_then(|path| strip_verbatim_prefix(&path))
+                    .unwrap_or(temp))
             }
 
             _ => {
@@ -211,6 +301,9 @@ impl StandardPaths {
                     LocationType::MusicLocation => FOLDERID_Music,
                     LocationType::MoviesLocation => FOLDERID_Videos,
                     LocationType::PicturesLocation => FOLDERID_Pictures,
+                    LocationType::TemplatesLocation => FOLDERID_Templates,
+                    LocationType::PublicShareLocation => FOLDERID_Public,
+                    LocationType::UserExecutablesLocation => FOLDERID_UserProgramFiles,
                     LocationType::AppLocalDataLocation
                     | LocationType::GenericDataLocation
                     | LocationType::ConfigLocation
@@ -237,12 +330,25 @@ impl StandardPaths {
                         }
                         Ok(path)
                     },
-                    { Err(Error::new(ErrorKind::Other, "Unexpected error")) }
+                    {
+                        Err(Error::Io {
+                            location: Some(location),
+                            path: None,
+                            source: io::Error::new(
+                                ErrorKind::NotFound,
+                                "Known folder is not available",
+                            ),
+                        })
+                    }
                 )
             }
         }
     }
 
+    pub(super) fn validate_environment_impl(&self) -> Vec<Error> {
+        Vec::new()
+    }
+
     #[inline]
     pub(super) fn standard_locations_impl(
         &self,
@@ -271,7 +377,11 @@ impl StandardPaths {
                 },
                 {}
             );
-            let path = env::current_exe()?;
+            let path = env::current_exe().map_err(|err| Error::Io {
+                location: Some(location),
+                path: None,
+                source: err,
+            })?;
             if let Some(parent) = path.parent() {
                 let mut parent: PathBuf = parent.into();
                 dirs.push(parent.clone());
@@ -283,9 +393,20 @@ impl StandardPaths {
     }
 }
 
+/// Removes the verbatim `\\?\` prefix [`std::fs::canonicalize`] adds to the paths.
+///
+/// Returns [`None`] if the path is not UTF-8.
+fn strip_verbatim_prefix(path: &Path) -> Option<PathBuf> {
+    let path = path.to_str()?;
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        return Some(PathBuf::from(format!(r"\\{rest}")));
+    }
+    Some(PathBuf::from(path.strip_prefix(r"\\?\").unwrap_or(path)))
+}
+
 /// Detect if `path` is an executable (based on
 /// [`GetBinaryType`](https://msdn.microsoft.com/ru-ru/library/windows/desktop/aa364819.aspx)).
-fn is_executable<P>(path: P) -> bool
+pub(crate) fn is_executable<P>(path: P) -> bool
 where
     P: AsRef<OsStr>,
 {
@@ -301,8 +422,13 @@ where
 }
 
 #[inline]
-pub(super) fn find_executable_in_paths_impl<S, P>(name: S, paths: P) -> Option<Vec<PathBuf>>
+pub(super) fn find_executable_in_paths_impl<F, S, P>(
+    fs: &F,
+    name: S,
+    paths: P,
+) -> Option<Vec<PathBuf>>
 where
+    F: FileSystem + ?Sized,
     S: Into<String>,
     P: AsRef<Vec<PathBuf>>,
 {
@@ -331,14 +457,14 @@ where
 
     // Check absolute paths
     if path.is_absolute() {
-        if is_executable(&name) {
+        if fs.is_executable(&path) {
             return Some(vec![path]);
         } else {
             let mut res = Vec::new();
             for ext in &exe_extensions {
                 let mut full_path = path.clone();
                 full_path.set_extension(ext);
-                if is_executable(&full_path) {
+                if fs.is_executable(&full_path) {
                     res.push(full_path);
                 }
             }
@@ -348,13 +474,19 @@ where
 
     // Check paths
     let mut paths = paths.as_ref().clone();
-    paths.retain(|p| !p.to_str().unwrap().is_empty() && p.is_dir());
+    paths.retain(|p| !p.as_os_str().is_empty() && fs.is_dir(p));
     let paths = {
         let mut paths2 = Vec::new();
         for path in paths {
+            let canonicalized = match fs.canonicalize(&path) {
+                Ok(path) => path,
+                _ => continue,
+            };
             // Remove '\\?\' prefix
-            let canonicalized = path.canonicalize().unwrap();
-            let path = PathBuf::from(canonicalized.to_str().unwrap().get(4..).unwrap());
+            let path = match canonicalized.to_str().and_then(|p| p.strip_prefix(r"\\?\")) {
+                Some(stripped) => PathBuf::from(stripped),
+                _ => canonicalized,
+            };
             if !paths2.contains(&path) {
                 paths2.push(path);
             }
@@ -366,7 +498,7 @@ where
     let mut res = Vec::new();
     for mut path in paths.iter().cloned() {
         path.push(&name);
-        if is_executable(&path) {
+        if fs.is_executable(&path) {
             res.push(path);
         }
     }
@@ -378,7 +510,7 @@ where
             for ext in &exe_extensions {
                 let mut full_path = path.clone();
                 full_path.set_extension(ext);
-                if is_executable(&full_path) {
+                if fs.is_executable(&full_path) {
                     res.push(full_path);
                 }
             }
@@ -391,3 +523,113 @@ where
         Some(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapEnvironment, MemoryFileSystem};
+
+    #[test]
+    fn state_locations() {
+        let sp = StandardPaths::new("app", "org");
+        let local = sp
+            .writable_location(LocationType::AppLocalDataLocation)
+            .unwrap();
+        assert_eq!(
+            sp.writable_location(LocationType::AppStateLocation)
+                .unwrap(),
+            local.join("State")
+        );
+        let generic = sp
+            .writable_location(LocationType::GenericDataLocation)
+            .unwrap();
+        assert_eq!(
+            sp.writable_location(LocationType::GenericStateLocation)
+                .unwrap(),
+            generic.join("State")
+        );
+    }
+
+    #[test]
+    fn state_locations_in_test_mode() {
+        let sp = StandardPaths::builder()
+            .app_name("app")
+            .org_name("org")
+            .test_root(r"C:\sandbox")
+            .build();
+        assert_eq!(
+            sp.writable_location(LocationType::AppStateLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\sandbox\state\org\app")
+        );
+        assert_eq!(
+            sp.writable_location(LocationType::GenericStateLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\sandbox\state")
+        );
+    }
+
+    #[test]
+    fn template_and_public_share_locations_in_test_mode() {
+        let sp = StandardPaths::builder().test_root(r"C:\sandbox").build();
+        assert_eq!(
+            sp.writable_location(LocationType::TemplatesLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\sandbox\home\Templates")
+        );
+        assert_eq!(
+            sp.writable_location(LocationType::PublicShareLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\sandbox\home\Public")
+        );
+    }
+
+    #[test]
+    fn user_executables_location() {
+        let sp = StandardPaths::new("app", "org");
+        assert!(sp
+            .writable_location(LocationType::UserExecutablesLocation)
+            .is_ok());
+
+        let sp = StandardPaths::builder().test_root(r"C:\sandbox").build();
+        assert_eq!(
+            sp.writable_location(LocationType::UserExecutablesLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\sandbox\bin")
+        );
+    }
+
+    #[test]
+    fn temp_location() {
+        let sp = |fs: MemoryFileSystem| {
+            StandardPaths::builder()
+                .environment(MapEnvironment::new().with_temp_dir(r"C:\Temp\..\Temp"))
+                .file_system(fs)
+                .build()
+        };
+        // A missing directory is returned as is
+        assert_eq!(
+            sp(MemoryFileSystem::new())
+                .writable_location(LocationType::TempLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\Temp\..\Temp")
+        );
+        assert_eq!(
+            sp(MemoryFileSystem::new().with_dir(r"C:\Temp"))
+                .writable_location(LocationType::TempLocation)
+                .unwrap(),
+            PathBuf::from(r"C:\Temp")
+        );
+    }
+
+    #[test]
+    fn verbatim_prefix() {
+        let strip = |path| strip_verbatim_prefix(Path::new(path));
+        assert_eq!(strip(r"\\?\C:\Temp"), Some(PathBuf::from(r"C:\Temp")));
+        assert_eq!(
+            strip(r"\\?\UNC\server\share\Temp"),
+            Some(PathBuf::from(r"\\server\share\Temp"))
+        );
+        assert_eq!(strip(r"C:\Temp"), Some(PathBuf::from(r"C:\Temp")));
+    }
+}
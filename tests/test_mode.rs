@@ -0,0 +1,112 @@
+//! Tests of the global test mode.
+//!
+//! The setting is shared by every instance in the process, so the tests
+//! live in their own binary and are serialized.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use standard_paths::{LocationType, MapEnvironment, MemoryFileSystem, StandardPaths};
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes the tests and disables the test mode when dropped.
+struct GlobalTestMode {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl GlobalTestMode {
+    fn lock() -> GlobalTestMode {
+        GlobalTestMode {
+            _lock: LOCK.lock().unwrap_or_else(PoisonError::into_inner),
+        }
+    }
+}
+
+impl Drop for GlobalTestMode {
+    fn drop(&mut self) {
+        StandardPaths::set_test_mode_enabled(false);
+    }
+}
+
+fn paths() -> StandardPaths {
+    StandardPaths::builder()
+        .app_name("app")
+        .environment(MapEnvironment::new().with_home("/home/user"))
+        .file_system(MemoryFileSystem::new())
+        .build()
+}
+
+#[test]
+fn enable_and_disable() {
+    let _guard = GlobalTestMode::lock();
+    let sp = paths();
+    assert!(!sp.is_test_mode_enabled());
+    assert_eq!(sp.test_root(), None);
+
+    // The existing instances follow the setting
+    StandardPaths::set_test_mode_enabled(true);
+    assert!(sp.is_test_mode_enabled());
+    assert_eq!(sp.test_root(), Some(PathBuf::from("/home/user/.qttest")));
+    #[cfg(target_os = "linux")]
+    assert_eq!(
+        sp.writable_location(LocationType::AppConfigLocation)
+            .unwrap(),
+        Path::new("/home/user/.qttest/config/app")
+    );
+
+    StandardPaths::set_test_mode_enabled(false);
+    assert!(!sp.is_test_mode_enabled());
+    assert_eq!(sp.test_root(), None);
+    #[cfg(target_os = "linux")]
+    assert_eq!(
+        sp.writable_location(LocationType::AppConfigLocation)
+            .unwrap(),
+        Path::new("/home/user/.config/app")
+    );
+}
+
+#[test]
+fn custom_root() {
+    let _guard = GlobalTestMode::lock();
+    StandardPaths::set_test_mode_root("/sandbox");
+    let sp = paths();
+    assert!(sp.is_test_mode_enabled());
+    assert_eq!(sp.test_root(), Some(PathBuf::from("/sandbox")));
+
+    // The instance root wins over the global one
+    let sp = StandardPaths::builder()
+        .test_root("/other")
+        .file_system(MemoryFileSystem::new())
+        .build();
+    assert_eq!(sp.test_root(), Some(PathBuf::from("/other")));
+
+    StandardPaths::set_test_mode_enabled(true);
+    assert_eq!(
+        paths().test_root(),
+        Some(PathBuf::from("/home/user/.qttest"))
+    );
+}
+
+#[test]
+fn sandbox_is_prepared_lazily() {
+    let _guard = GlobalTestMode::lock();
+    let root = std::env::temp_dir().join(format!(
+        "standard_paths_global_test_mode.{}",
+        std::process::id()
+    ));
+    StandardPaths::set_test_mode_root(&root);
+    // Neither enabling the test mode nor constructing an instance touches the disk
+    let sp = StandardPaths::new("app", "org");
+    assert!(!root.exists());
+
+    let config = sp
+        .writable_location(LocationType::AppConfigLocation)
+        .unwrap();
+    assert_eq!(config, root.join("config/org/app"));
+    #[cfg(target_os = "linux")]
+    assert!(root.join("config/user-dirs.dirs").is_file());
+    std::fs::remove_dir_all(&root).ok();
+}